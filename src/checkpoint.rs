@@ -0,0 +1,157 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::mpsc::RecvTimeoutError,
+    thread::{self, JoinHandle},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use serde::{Deserialize, Serialize};
+use crate::{
+    history::{Entry, History, Outcome},
+    paths,
+    timer::RunState,
+    worker::Handle,
+};
+
+/// How often a running session is saved even when nothing happens.
+const CHECKPOINT_EVERY: Duration = Duration::from_secs(30);
+
+/// A session in progress, as saved to disk so it can outlive the process.
+/// Times are Unix time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub run_state: RunState,
+    /// Index into the custom sequence, if there is one.
+    #[serde(default)]
+    pub step: u8,
+    pub cur_lap: u8,
+    pub cur_loop: u8,
+    /// Lap and loop for the history, as in [`Entry`].
+    pub lap: u8,
+    #[serde(rename = "loop")]
+    pub cycle: u8,
+    /// When the phase was due to end, in milliseconds. Only set while the
+    /// countdown was running rather than paused.
+    pub deadline_ms: Option<u64>,
+    /// Time left in the phase when this was saved.
+    pub remaining_ms: u64,
+    pub planned_sec: u64,
+    /// Part of `planned_sec` added on with extends.
+    #[serde(default)]
+    pub extended_sec: u64,
+    pub phase_started: Option<u64>,
+    pub saved_at: u64,
+}
+
+pub fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |since| since.as_millis() as u64)
+}
+
+impl Checkpoint {
+    /// Time left if the session were picked up at `now`. A running phase kept
+    /// counting down while the app was gone.
+    pub fn remaining_at(&self, now: SystemTime) -> Duration {
+        match self.deadline_ms {
+            Some(deadline) => Duration::from_millis(deadline.saturating_sub(unix_millis(now))),
+            None => Duration::from_millis(self.remaining_ms),
+        }
+    }
+
+    /// What the history should say about the interrupted phase when it is
+    /// thrown away instead of resumed.
+    pub fn abandoned(&self) -> Option<Entry> {
+        let started_at = self.phase_started?;
+        Some(Entry {
+            phase: self.run_state,
+            lap: self.lap,
+            cycle: self.cycle,
+            started_at,
+            ended_at: self.saved_at,
+            planned_sec: self.planned_sec,
+            extended_sec: self.extended_sec,
+            actual_sec: self.planned_sec.saturating_sub(self.remaining_ms / 1000),
+            outcome: Outcome::Aborted,
+        })
+    }
+
+    /// `$XDG_STATE_HOME/pomodoro_rs/session.json`.
+    pub fn path() -> Option<PathBuf> {
+        paths::state_dir().map(|dir| dir.join("session.json"))
+    }
+
+    /// The session left behind by the last run, if any. A file that can't be
+    /// read is treated as no session at all.
+    pub fn load(path: &Path) -> Option<Self> {
+        let text = fs::read_to_string(path).ok()?;
+        serde_json::from_str(&text).ok()
+    }
+
+    /// Saves `checkpoint`, or removes the file when there is no session.
+    pub fn store(path: &Path, checkpoint: Option<&Self>) -> io::Result<()> {
+        let Some(checkpoint) = checkpoint else {
+            return match fs::remove_file(path) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+                _ => Ok(()),
+            };
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec(checkpoint)?)?;
+        fs::rename(&tmp, path)
+    }
+
+    /// Throws the session away for good: removes it from `path` straight
+    /// away so it isn't offered again, then logs the interrupted phase to
+    /// `history` as aborted.
+    pub fn discard(&self, path: &Path, history: Option<&History>) {
+        if let Err(err) = Self::store(path, None) {
+            eprintln!("pomodoro: could not remove {}: {}", path.display(), err);
+        }
+        if let (Some(history), Some(entry)) = (history, self.abandoned())
+            && let Err(err) = history.append(&entry)
+        {
+            eprintln!("pomodoro: could not write {}: {}", history.path().display(), err);
+        }
+    }
+}
+
+/// Keeps the checkpoint file in step with a worker: rewritten on every event
+/// and every [`CHECKPOINT_EVERY`] in between, removed once the session ends.
+/// Stops along with the worker; dropping waits for the last write.
+pub struct Checkpointer {
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Checkpointer {
+    pub fn spawn(path: PathBuf, handle: Handle) -> Self {
+        let events = handle.subscribe();
+        let thread = thread::spawn(move || {
+            let mut written: Option<Option<Checkpoint>> = None;
+            loop {
+                let done = events.recv_timeout(CHECKPOINT_EVERY) == Err(RecvTimeoutError::Disconnected);
+                let checkpoint = handle.checkpoint();
+                // no need to keep deleting the file while the timer sits idle
+                if checkpoint.is_some() || written != Some(checkpoint) {
+                    if let Err(err) = Checkpoint::store(&path, checkpoint.as_ref()) {
+                        eprintln!("pomodoro: could not write {}: {}", path.display(), err);
+                    }
+                    written = Some(checkpoint);
+                }
+                if done {
+                    break;
+                }
+            }
+        });
+        Self { thread: Some(thread) }
+    }
+}
+
+impl Drop for Checkpointer {
+    fn drop(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
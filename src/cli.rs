@@ -0,0 +1,121 @@
+use std::{
+    io::{self, BufRead, Write},
+    process::ExitCode,
+    sync::{Arc, atomic::{AtomicBool, Ordering}, mpsc::{self, Receiver}},
+    thread,
+    time::Duration,
+};
+use clap::Args;
+use pomodoro_rs::{
+    duration,
+    services::Services,
+    settings::Settings,
+    timer::{Event, RunState, Status},
+    worker::{Command, Worker},
+};
+
+#[derive(Args)]
+pub struct RunArgs {
+    /// Lap length, like 50m, 1h30m or 45s; a plain number is minutes
+    #[arg(long, value_name = "TIME", value_parser = duration::parse)]
+    lap: Option<Duration>,
+    /// Rest between laps
+    #[arg(long, value_name = "TIME", value_parser = duration::parse)]
+    short: Option<Duration>,
+    /// Rest after the last lap of a loop
+    #[arg(long, value_name = "TIME", value_parser = duration::parse)]
+    long: Option<Duration>,
+    /// Laps before the long rest
+    #[arg(long, value_name = "N")]
+    laps: Option<u8>,
+}
+
+/// Runs the usual lap/rest cycle with a live countdown on one terminal line.
+/// Anything not given on the command line comes from the settings file.
+pub fn run(args: RunArgs) -> ExitCode {
+    let (mut settings, err) = Settings::load_or_default();
+    if let Some(err) = err {
+        eprintln!("pomodoro: {}, using the defaults", err);
+    }
+    let config = &mut settings.timer;
+    config.lap = args.lap.unwrap_or(config.lap);
+    config.rest_lap = args.short.unwrap_or(config.rest_lap);
+    config.rest_loop = args.long.unwrap_or(config.rest_loop);
+    config.laps_per_loop = args.laps.unwrap_or(config.laps_per_loop);
+    if let Err(err) = settings.validate() {
+        eprintln!("pomodoro: {}", err);
+        return ExitCode::FAILURE;
+    }
+
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&interrupted);
+    if let Err(err) = ctrlc::set_handler(move || flag.store(true, Ordering::Relaxed)) {
+        eprintln!("pomodoro: could not catch Ctrl-C: {}", err);
+    }
+
+    let services = Services::spawn(&settings, Worker::spawn(settings.new_timer(), || {}));
+    if let Some(err) = services.sound_error() {
+        eprintln!("pomodoro: no sound: {}", err);
+    }
+    let worker = services.worker();
+    let events = worker.subscribe();
+    let lines = stdin_lines();
+    println!("Enter pauses and resumes, s skips, q or Ctrl-C quits");
+    worker.send(Command::Start);
+
+    while !interrupted.load(Ordering::Relaxed) {
+        for event in events.try_iter() {
+            if let Event::PhaseEnded { next, .. } = event {
+                print!("\r\x1b[K");
+                match (next, worker.status().pause) {
+                    (RunState::LAP, false) => println!("rest over, back to work"),
+                    (RunState::LAP, true) => println!("rest over, Enter starts the next lap"),
+                    (_, false) => println!("lap over, resting"),
+                    (_, true) => println!("lap over, Enter starts the rest"),
+                }
+            }
+        }
+        match lines.try_recv().as_deref().map(str::trim) {
+            Ok("") if worker.status().pause => worker.send(Command::Resume),
+            Ok("") => worker.send(Command::Pause),
+            Ok("s") => worker.send(Command::Skip),
+            Ok("q") => break,
+            _ => {}
+        }
+        draw(&worker.status());
+        thread::sleep(Duration::from_millis(200));
+    }
+
+    // quitting abandons the session, which the history should hear about
+    worker.send(Command::Stop);
+    drop(services);
+    println!();
+    ExitCode::SUCCESS
+}
+
+fn draw(status: &Status) {
+    let paused = if status.pause { "  (paused)" } else { "" };
+    print!(
+        "\r\x1b[K{} {}  Lap: {}/{}, Loop {}{}",
+        status.label,
+        status.remaining_clock(),
+        status.cur_lap,
+        status.laps_per_loop,
+        status.cur_loop,
+        paused,
+    );
+    let _ = io::stdout().flush();
+}
+
+fn stdin_lines() -> Receiver<String> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
@@ -0,0 +1,87 @@
+use std::{
+    io::{self, Write},
+    process::ExitCode,
+    thread,
+    time::Duration,
+};
+use pomodoro_rs::{
+    ipc::{self, Request, Response},
+    paths,
+    statusbar::{self, Format},
+};
+
+/// The request was understood but refused, e.g. pausing a paused timer.
+const REFUSED: u8 = 1;
+/// Nothing is listening on the control socket.
+const NO_TIMER: u8 = 3;
+
+/// Sends `request` to the running GUI or daemon, prints the JSON answer on
+/// stdout and turns it into an exit code.
+pub fn run(request: Request) -> ExitCode {
+    let response = match send(&request) {
+        Ok(response) => response,
+        Err(code) => return code,
+    };
+    match serde_json::to_string(&response) {
+        Ok(line) => println!("{}", line),
+        Err(err) => eprintln!("pomodoro: {}", err),
+    }
+    if response.ok {
+        ExitCode::SUCCESS
+    } else {
+        if let Some(err) = &response.error {
+            eprintln!("pomodoro: {}", err);
+        }
+        ExitCode::from(REFUSED)
+    }
+}
+
+/// A second timer next to the window or daemon already on the control socket
+/// would run on its own and fight it over the session checkpoint, so the
+/// frontends that own a timer check with this first and say why they won't.
+pub fn already_running() -> bool {
+    let Some(socket) = paths::socket_path().filter(|socket| ipc::answers(socket)) else {
+        return false;
+    };
+    eprintln!(
+        "pomodoro: a timer is already running on {}, use `pomodoro_rs start|pause|resume|stop|skip|status` \
+         to control it or stop it first",
+        socket.display()
+    );
+    true
+}
+
+pub fn send(request: &Request) -> Result<Response, ExitCode> {
+    let Some(socket) = paths::socket_path() else {
+        eprintln!("pomodoro: nowhere to look for the control socket (is $HOME set?)");
+        return Err(ExitCode::from(NO_TIMER));
+    };
+    ipc::request(&socket, request).map_err(|err| {
+        eprintln!("pomodoro: no timer running on {}: {}", socket.display(), err);
+        ExitCode::from(NO_TIMER)
+    })
+}
+
+/// Prints the running timer's status once, or every second with `follow`,
+/// the way [`statusbar::render`] lays it out.
+pub fn status(format: Format, template: Option<String>, follow: bool) -> ExitCode {
+    let mut stdout = io::stdout();
+    loop {
+        let report = match send(&Request::Status) {
+            Ok(response) => response.status,
+            Err(code) if !follow => {
+                let _ = writeln!(stdout, "{}", statusbar::render(format, template.as_deref(), None));
+                return code;
+            }
+            Err(_) => None,
+        };
+        if writeln!(stdout, "{}", statusbar::render(format, template.as_deref(), report.as_ref())).is_err() {
+            // the bar went away
+            return ExitCode::SUCCESS;
+        }
+        if !follow {
+            return ExitCode::SUCCESS;
+        }
+        thread::sleep(Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,70 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Where the timer gets the current time from.
+///
+/// `now` is monotonic and drives the countdown. `wall` is only used to notice
+/// time the monotonic clock missed, which on Linux is any time spent in
+/// system suspend.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+    fn wall(&self) -> SystemTime;
+}
+
+/// The real clocks.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn wall(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock that only moves when told to, for driving the timer in tests.
+#[derive(Debug)]
+pub struct FakeClock {
+    now: Mutex<(Instant, SystemTime)>,
+}
+
+impl Default for FakeClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FakeClock {
+    pub fn new() -> Self {
+        Self {
+            now: Mutex::new((Instant::now(), SystemTime::now())),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        let mut now = self.now.lock().unwrap();
+        now.0 += by;
+        now.1 += by;
+    }
+
+    /// Moves only the wall clock, the way a suspended machine sees it after
+    /// waking up.
+    pub fn suspend(&self, by: Duration) {
+        self.now.lock().unwrap().1 += by;
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+        self.now.lock().unwrap().0
+    }
+
+    fn wall(&self) -> SystemTime {
+        self.now.lock().unwrap().1
+    }
+}
@@ -0,0 +1,72 @@
+use std::{process::ExitCode, sync::mpsc};
+use clap::Args;
+use pomodoro_rs::{
+    paths,
+    services::Services,
+    settings::Settings,
+    worker::Worker,
+};
+
+#[derive(Args)]
+pub struct DaemonArgs {
+    /// Also serve the HTTP API on this port of 127.0.0.1
+    #[cfg(feature = "http")]
+    #[arg(long, value_name = "PORT")]
+    http: Option<u16>,
+}
+
+/// Owns the timer without any window and serves it on the control socket
+/// until interrupted. A session the last daemon was running when it went
+/// down is picked back up, paused.
+pub fn run(args: DaemonArgs) -> ExitCode {
+    let Some(socket) = paths::socket_path() else {
+        eprintln!("pomodoro: nowhere to put the control socket (is $HOME set?)");
+        return ExitCode::FAILURE;
+    };
+    let (settings, err) = Settings::load_or_default();
+    if let Some(err) = err {
+        eprintln!("pomodoro: {}, using the defaults", err);
+    }
+
+    let mut services = Services::spawn(&settings, Worker::spawn(settings.new_timer(), || {}));
+    // before touching the checkpoint, which belongs to whoever has the socket
+    if let Err(err) = services.serve(&socket) {
+        eprintln!("pomodoro: {}", err);
+        return ExitCode::FAILURE;
+    }
+    if services.take_checkpoint().is_some() {
+        services.restore();
+    }
+    if let Some(err) = services.sound_error() {
+        eprintln!("pomodoro: no sound: {}", err);
+    }
+    eprintln!("pomodoro: listening on {}", socket.display());
+    #[cfg(feature = "dbus")]
+    if let Err(err) = services.serve_dbus() {
+        eprintln!("pomodoro: not on D-Bus: {}", err);
+    }
+
+    #[cfg(feature = "http")]
+    match args.http.map(|port| services.serve_http(port)).transpose() {
+        Ok(Some(Some(addr))) => eprintln!("pomodoro: HTTP API on http://{}", addr),
+        Ok(_) => {}
+        Err(err) => {
+            eprintln!("pomodoro: no HTTP API: {}", err);
+            return ExitCode::FAILURE;
+        }
+    }
+    // nothing else to set up for now
+    #[cfg(not(feature = "http"))]
+    let DaemonArgs {} = args;
+
+    let (tx, interrupted) = mpsc::channel();
+    if let Err(err) = ctrlc::set_handler(move || {
+        let _ = tx.send(());
+    }) {
+        eprintln!("pomodoro: could not catch Ctrl-C: {}", err);
+    }
+    let _ = interrupted.recv();
+
+    drop(services);
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,177 @@
+use std::{
+    sync::mpsc::{self, RecvTimeoutError, Sender},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+use zbus::{
+    blocking::{Connection, connection::Builder, object_server::InterfaceRef},
+    fdo, interface,
+    object_server::SignalEmitter,
+};
+use crate::{
+    ipc::{Request, handle_request},
+    timer::Event,
+    worker::Handle,
+};
+
+/// The well-known name the timer takes on the session bus.
+pub const NAME: &str = "io.github.NeiwKai.Pomodoro";
+pub const PATH: &str = "/io/github/NeiwKai/Pomodoro";
+pub const INTERFACE: &str = "io.github.NeiwKai.Pomodoro1";
+
+/// The object behind [`PATH`]. Methods are refused the same way as on the
+/// control socket, e.g. `Pause` on a paused timer fails.
+struct Pomodoro {
+    handle: Handle,
+}
+
+impl Pomodoro {
+    fn request(&self, request: Request) -> fdo::Result<()> {
+        let response = handle_request(&self.handle, request);
+        match response.error {
+            Some(err) if !response.ok => Err(fdo::Error::Failed(err)),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[interface(name = "io.github.NeiwKai.Pomodoro1")]
+impl Pomodoro {
+    fn start(&self) -> fdo::Result<()> {
+        self.request(Request::Start)
+    }
+
+    fn pause(&self) -> fdo::Result<()> {
+        self.request(Request::Pause)
+    }
+
+    fn resume(&self) -> fdo::Result<()> {
+        self.request(Request::Resume)
+    }
+
+    fn stop(&self) -> fdo::Result<()> {
+        self.request(Request::Stop)
+    }
+
+    fn skip(&self) -> fdo::Result<()> {
+        self.request(Request::Skip)
+    }
+
+    #[zbus(property)]
+    fn remaining_seconds(&self) -> u32 {
+        self.handle.status().remaining_sec()
+    }
+
+    /// `lap`, `rest_lap` or `rest_loop`.
+    #[zbus(property, name = "Phase")]
+    fn run_state(&self) -> String {
+        self.handle.status().run_state.name().to_owned()
+    }
+
+    /// `stopped`, `paused` or `running`.
+    #[zbus(property)]
+    fn state(&self) -> String {
+        let status = self.handle.status();
+        match (status.running, status.pause) {
+            (false, _) => "stopped",
+            (true, true) => "paused",
+            (true, false) => "running",
+        }.to_owned()
+    }
+
+    #[zbus(property)]
+    fn lap(&self) -> u32 {
+        self.handle.status().cur_lap as u32
+    }
+
+    #[zbus(property, name = "Loop")]
+    fn cycle(&self) -> u32 {
+        self.handle.status().cur_loop as u32
+    }
+
+    /// A phase ended, by running out or by being skipped.
+    #[zbus(signal, name = "PhaseChanged")]
+    async fn phase_ended(emitter: &SignalEmitter<'_>, ended: &str, next: &str) -> zbus::Result<()>;
+
+    /// Once a second while counting down.
+    #[zbus(signal)]
+    async fn tick(emitter: &SignalEmitter<'_>, remaining_seconds: u32) -> zbus::Result<()>;
+}
+
+/// Serves a worker on D-Bus and turns its events into signals. Dropping it
+/// releases the name.
+pub struct Service {
+    stop: Sender<()>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Service {
+    /// Takes [`NAME`] on the session bus; fails if another timer has it.
+    pub fn spawn(handle: Handle) -> zbus::Result<Self> {
+        Self::serve(Builder::session()?, handle)
+    }
+
+    /// Like [`Service::spawn`], on the bus at `address` instead.
+    pub fn spawn_at(address: &str, handle: Handle) -> zbus::Result<Self> {
+        Self::serve(Builder::address(address)?, handle)
+    }
+
+    fn serve(builder: Builder<'_>, handle: Handle) -> zbus::Result<Self> {
+        let events = handle.subscribe();
+        let connection = builder
+            .name(NAME)?
+            .serve_at(PATH, Pomodoro { handle: handle.clone() })?
+            .build()?;
+        let object = connection.object_server().interface::<_, Pomodoro>(PATH)?;
+        let (stop, stopped) = mpsc::channel();
+        let thread = thread::spawn(move || {
+            // the connection goes, and the name with it, when this returns
+            let _connection: Connection = connection;
+            let mut shown = None;
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(Duration::from_millis(200)) {
+                let mut changed = false;
+                for event in events.try_iter() {
+                    if let Event::PhaseEnded { ended, next } = event {
+                        let _ = zbus::block_on(Pomodoro::phase_ended(object.signal_emitter(), ended.name(), next.name()));
+                    }
+                    changed = true;
+                }
+                if changed {
+                    announce(&object);
+                }
+                let status = handle.status();
+                let remaining = status.remaining_sec();
+                if shown != Some(remaining) {
+                    shown = Some(remaining);
+                    if status.running && !status.pause {
+                        let _ = zbus::block_on(Pomodoro::tick(object.signal_emitter(), remaining));
+                    }
+                    let _ = zbus::block_on(object.get().remaining_seconds_changed(object.signal_emitter()));
+                }
+            }
+        });
+        Ok(Self {
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
+
+/// Tells property watchers that everything but the countdown may have moved.
+fn announce(object: &InterfaceRef<Pomodoro>) {
+    let emitter = object.signal_emitter();
+    let pomodoro = object.get();
+    let _ = zbus::block_on(pomodoro.phase_changed(emitter));
+    let _ = zbus::block_on(pomodoro.state_changed(emitter));
+    let _ = zbus::block_on(pomodoro.lap_changed(emitter));
+    let _ = zbus::block_on(pomodoro.loop_changed(emitter));
+}
+
+impl Drop for Service {
+    fn drop(&mut self) {
+        let _ = self.stop.send(());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+/// Reads durations written the way people write them: `25m`, `1h30m`,
+/// `45s`, `1h 5m 30s`. A bare number is minutes.
+pub fn parse(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("empty duration".to_owned());
+    }
+    if let Ok(min) = text.parse::<u64>() {
+        return min
+            .checked_mul(60)
+            .map(Duration::from_secs)
+            .ok_or_else(|| format!("\"{}\" is too long", text));
+    }
+    let mut total = 0u64;
+    let mut number = String::new();
+    for c in text.chars().filter(|c| !c.is_whitespace()) {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(format!("unknown unit '{}' in \"{}\", use h, m or s", c, text)),
+        };
+        let value: u64 = number
+            .parse()
+            .map_err(|_| format!("missing number before '{}' in \"{}\"", c, text))?;
+        total = value
+            .checked_mul(unit)
+            .and_then(|secs| total.checked_add(secs))
+            .ok_or_else(|| format!("\"{}\" is too long", text))?;
+        number.clear();
+    }
+    if !number.is_empty() {
+        return Err(format!("missing unit after {} in \"{}\"", number, text));
+    }
+    Ok(Duration::from_secs(total))
+}
+
+/// The other way round from [`parse`]: `25m`, `1h30m`, `45s`. Anything under
+/// a second is dropped.
+pub fn format(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs == 0 {
+        return "0s".to_owned();
+    }
+    [(secs / 3600, 'h'), (secs / 60 % 60, 'm'), (secs % 60, 's')]
+        .into_iter()
+        .filter(|(value, _)| *value > 0)
+        .map(|(value, unit)| format!("{}{}", value, unit))
+        .collect()
+}
+
+/// `secs` as a countdown shows it: `MM:SS`, or `H:MM:SS` from an hour up.
+pub fn clock(secs: u32) -> String {
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{:02}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// For `#[serde(with = "duration::human")]`: written out by [`format`] and
+/// read back by [`parse`], so a plain number still counts as minutes.
+pub mod human {
+    use std::{fmt, time::Duration};
+    use serde::{Deserializer, Serializer, de::{self, Visitor}};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::format(*duration))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        deserializer.deserialize_any(Human)
+    }
+
+    struct Human;
+
+    impl<'de> Visitor<'de> for Human {
+        type Value = Duration;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a duration like \"25m\" or \"1h30m\", or a number of minutes")
+        }
+
+        fn visit_u64<E: de::Error>(self, min: u64) -> Result<Duration, E> {
+            min.checked_mul(60).map(Duration::from_secs).ok_or_else(|| E::custom("duration too long"))
+        }
+
+        fn visit_i64<E: de::Error>(self, min: i64) -> Result<Duration, E> {
+            let min = u64::try_from(min).map_err(|_| E::custom("a duration can't be negative"))?;
+            self.visit_u64(min)
+        }
+
+        fn visit_str<E: de::Error>(self, text: &str) -> Result<Duration, E> {
+            super::parse(text).map_err(E::custom)
+        }
+    }
+
+    /// The same for an `Option<Duration>`.
+    pub mod option {
+        use std::time::Duration;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        #[derive(Deserialize)]
+        struct Human(#[serde(with = "crate::duration::human")] Duration);
+
+        pub fn serialize<S: Serializer>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+            match duration {
+                Some(duration) => serializer.serialize_some(&crate::duration::format(*duration)),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+            Ok(Option::<Human>::deserialize(deserializer)?.map(|Human(duration)| duration))
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use std::time::Duration;
+use eframe::egui;
+use pomodoro_rs::{
+    checkpoint::Checkpoint,
+    duration,
+    history::History,
+    paths,
+    services::Services,
+    settings::{Error, Settings},
+    sound::Sounds,
+    stats::Stats,
+    timer::Config,
+    worker::{Command, Worker},
+};
+
+pub fn run() -> eframe::Result<()> {
+    let options = eframe::NativeOptions{
+        viewport: egui::ViewportBuilder::default().with_inner_size([500.0, 500.0]),
+        ..Default::default()
+    };
+    eframe::run_native(
+        "Pomodoro",
+        options,
+        Box::new(|cc: &eframe::CreationContext<'_>| {
+            Ok(Box::new(MyApp::new(cc.egui_ctx.clone())))
+        })
+    )
+}
+
+#[allow(clippy::upper_case_acronyms)]
+enum State {
+    STEADY,
+    SETTING,
+    STATS,
+    RECOVER,
+}
+
+/// How many days the bar chart on the statistics screen goes back.
+const STATS_DAYS: u64 = 14;
+
+struct MyApp {
+    app_state: State,
+    services: Services,
+    recovered: Option<Checkpoint>,
+    stats: Stats,
+    stats_error: Option<String>,
+    settings: Settings,
+    settings_error: Option<String>,
+    /// `config.toml` failed to load, so it gets backed up before the first
+    /// save rather than overwritten with the defaults.
+    config_broken: bool,
+    /// Durations as typed, e.g. `25m` or `1h30m`.
+    lap: String,
+    rest_lap: String,
+    rest_loop: String,
+    laps_per_loop: u8,
+    auto_start_breaks: bool,
+    auto_start_laps: bool,
+    auto_start_loop: bool,
+    volume: u8,
+    ticking: bool,
+}
+
+impl MyApp {
+    fn new(ctx: egui::Context) -> Self {
+        let (settings, settings_error) = Settings::load_or_default();
+        let config = settings.timer;
+        let worker = Worker::spawn(settings.new_timer(), move || ctx.request_repaint());
+        let mut services = Services::spawn(&settings, worker);
+        if let Some(socket) = paths::socket_path()
+            && let Err(err) = services.serve(&socket)
+        {
+            eprintln!("pomodoro: not listening for commands: {}", err);
+        }
+        #[cfg(feature = "dbus")]
+        if let Err(err) = services.serve_dbus() {
+            eprintln!("pomodoro: not on D-Bus: {}", err);
+        }
+        // the old checkpoint is left alone until the user has decided about it
+        let recovered = services.take_checkpoint();
+        Self {
+            app_state: if recovered.is_some() {
+                State::RECOVER
+            } else if settings_error.is_some() {
+                // make sure a broken config file gets noticed
+                State::SETTING
+            } else {
+                State::STEADY
+            },
+            services,
+            recovered,
+            stats: Stats::default(),
+            stats_error: None,
+            volume: settings.sounds.volume,
+            ticking: settings.sounds.ticking,
+            settings,
+            config_broken: settings_error.is_some(),
+            settings_error: settings_error.map(|err| err.to_string()),
+            lap: duration::format(config.lap),
+            rest_lap: duration::format(config.rest_lap),
+            rest_loop: duration::format(config.rest_loop),
+            laps_per_loop: config.laps_per_loop,
+            auto_start_breaks: config.auto_start_breaks,
+            auto_start_laps: config.auto_start_laps,
+            auto_start_loop: config.auto_start_loop,
+        }
+    }
+
+    fn steady(&mut self, ui: &mut egui::Ui) {
+        let status = self.services.worker().status();
+        let time = status.remaining_sec();
+        ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
+            let duration_time = duration::clock(time);
+            ui.label(egui::RichText::new(&status.label).font(egui::FontId::proportional(10.0)));
+            ui.label(egui::RichText::new(duration_time.to_string()).font(egui::FontId::proportional(100.0)));
+            ui.label(egui::RichText::new(format!("Lap: {}/{}, Loop {}", status.cur_lap, status.laps_per_loop, status.cur_loop)).font(egui::FontId::proportional(20.0)));
+            ui.add_space(100.0);
+            if status.pause {
+                if ui.button(egui::RichText::new("▶").font(egui::FontId::proportional(30.0))).clicked() {
+                    self.services.worker().send(if status.running { Command::Resume } else { Command::Start });
+                }
+            } else {
+                if ui.button(egui::RichText::new("⏸").font(egui::FontId::proportional(30.0))).clicked() {
+                    self.services.worker().send(Command::Pause);
+                }
+            } 
+            if status.running {
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if ui.button(egui::RichText::new("⏭").font(egui::FontId::proportional(20.0))).on_hover_text("skip to the next phase").clicked() {
+                        self.services.worker().send(Command::Skip);
+                    }
+                    for (min, hint) in [(1, "one more minute"), (5, "five more minutes")] {
+                        if ui.button(egui::RichText::new(format!("+{}", min)).font(egui::FontId::proportional(20.0))).on_hover_text(hint).clicked() {
+                            self.services.worker().send(Command::Extend(Duration::from_secs(min * 60)));
+                        }
+                    }
+                });
+            }
+            ui.add_space(20.0);
+            if !status.running {
+                if ui.button(egui::RichText::new("⚙").font(egui::FontId::proportional(30.0))).clicked() {
+                    self.app_state = State::SETTING;
+                }
+            } else if status.pause && ui.button(egui::RichText::new("⏹").font(egui::FontId::proportional(30.0))).clicked() {
+                self.services.worker().send(Command::Stop);
+            }
+            ui.add_space(20.0);
+            if ui.button(egui::RichText::new("📊").font(egui::FontId::proportional(30.0))).clicked() {
+                self.refresh_stats();
+                self.app_state = State::STATS;
+            }
+        });
+    }
+    fn recover(&mut self, ui: &mut egui::Ui) {
+        let Some(checkpoint) = self.recovered else {
+            self.app_state = State::STEADY;
+            return;
+        };
+        let status = self.settings.preview(&checkpoint);
+        ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
+            ui.label("unfinished session");
+            ui.add_space(50.0);
+            ui.label(egui::RichText::new(status.label.as_str()).font(egui::FontId::proportional(10.0)));
+            ui.label(egui::RichText::new(status.remaining_clock()).font(egui::FontId::proportional(60.0)));
+            ui.label(egui::RichText::new(format!("Lap: {}/{}, Loop {}", status.cur_lap, status.laps_per_loop, status.cur_loop)).font(egui::FontId::proportional(20.0)));
+            ui.add_space(50.0);
+            let resume = ui.button(egui::RichText::new("resume").font(egui::FontId::proportional(20.0))).clicked();
+            ui.add_space(10.0);
+            let discard = ui.button(egui::RichText::new("discard").font(egui::FontId::proportional(20.0))).clicked();
+            if resume {
+                self.services.restore();
+                self.services.worker().send(Command::Resume);
+            } else if discard {
+                self.services.discard();
+            }
+            if resume || discard {
+                self.recovered = None;
+                self.app_state = State::STEADY;
+            }
+        });
+    }
+    fn refresh_stats(&mut self) {
+        let entries = match self.services.history().map(History::load) {
+            Some(Ok(entries)) => entries,
+            Some(Err(err)) => {
+                self.stats_error = Some(err.to_string());
+                Vec::new()
+            }
+            None => {
+                self.stats_error = Some("no data directory to keep history in (is $HOME set?)".to_owned());
+                Vec::new()
+            }
+        };
+        self.stats = Stats::today(&entries, STATS_DAYS);
+    }
+    fn statistics(&mut self, ui: &mut egui::Ui) {
+        ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
+            ui.label("statistics");
+            ui.add_space(30.0);
+            let stats = &self.stats;
+            ui.label(format!("Pomodoros today: {}", stats.completed_today));
+            ui.label(format!("Pomodoros this week: {}", stats.completed_this_week));
+            ui.label(format!("Total focus: {}h {:02}m", stats.focus_minutes / 60, stats.focus_minutes % 60));
+            match stats.completion_rate {
+                Some(rate) => ui.label(format!("Completion rate: {:.0}%", rate * 100.0)),
+                None => ui.label("Completion rate: -"),
+            };
+            ui.label(format!("Longest streak: {} days", stats.longest_streak));
+            ui.add_space(20.0);
+            bar_chart(ui, &stats.per_day);
+            ui.add_space(20.0);
+            if let Some(err) = &self.stats_error {
+                ui.colored_label(ui.visuals().error_fg_color, err);
+                ui.add_space(10.0);
+            }
+            if ui.button("back").clicked() {
+                self.stats_error = None;
+                self.app_state = State::STEADY;
+            }
+        });
+    }
+    fn setting(&mut self, ui: &mut egui::Ui) {
+        ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
+            ui.label("setting");
+            ui.add_space(50.0);
+            ui.with_layout(egui::Layout::left_to_right(egui::Align::TOP), |ui| {
+                ui.label("Lap duration: ");
+                ui.add(egui::TextEdit::singleline(&mut self.lap).desired_width(80.0).hint_text("25m"));
+            });
+            ui.with_layout(egui::Layout::left_to_right(egui::Align::TOP), |ui| {
+                ui.label("Lap rest duration: ");
+                ui.add(egui::TextEdit::singleline(&mut self.rest_lap).desired_width(80.0).hint_text("5m"));
+            });
+            ui.with_layout(egui::Layout::left_to_right(egui::Align::TOP), |ui| {
+                ui.label("Loop rest duration: ");
+                ui.add(egui::TextEdit::singleline(&mut self.rest_loop).desired_width(80.0).hint_text("30m"));
+            });
+            ui.with_layout(egui::Layout::left_to_right(egui::Align::TOP), |ui| {
+                ui.label("Laps before loop rest: ");
+                ui.add(egui::DragValue::new(&mut self.laps_per_loop).range(1..=12).speed(1));
+                ui.label("laps");
+            });
+            if !self.settings.sequence.is_empty() {
+                ui.label("(the sequence in config.toml is used instead of these)");
+            }
+            ui.checkbox(&mut self.auto_start_breaks, "Start rests by themselves");
+            ui.checkbox(&mut self.auto_start_laps, "Start laps after a lap rest by themselves");
+            ui.checkbox(&mut self.auto_start_loop, "Start the next loop by itself");
+            let sound_error = self.services.sound_error().map(str::to_owned);
+            ui.add_enabled_ui(sound_error.is_none(), |ui| {
+                ui.with_layout(egui::Layout::left_to_right(egui::Align::TOP), |ui| {
+                    ui.label("Volume: ");
+                    ui.add(egui::Slider::new(&mut self.volume, 0..=100).suffix("%"));
+                });
+                ui.checkbox(&mut self.ticking, "Tick during laps");
+            });
+            if let Some(err) = sound_error {
+                ui.label(format!("(no sound: {})", err));
+            }
+            ui.add_space(25.0);
+            if let Some(err) = &self.settings_error {
+                ui.colored_label(ui.visuals().error_fg_color, err);
+                ui.add_space(10.0);
+            }
+            if ui.button("confirm").clicked() {
+                let applied = self.edited_settings().and_then(|settings| {
+                    let applied = settings.apply(self.config_broken, &self.services.worker().handle(), self.services.player());
+                    match applied {
+                        Err(Error::Invalid(reason)) => Err(reason),
+                        applied => Ok((settings, applied)),
+                    }
+                });
+                match applied {
+                    Err(err) => self.settings_error = Some(err),
+                    Ok((settings, applied)) => {
+                        self.config_broken &= applied.is_err();
+                        self.settings_error = match applied {
+                            Ok(Some(backup)) => Some(format!("the old config is kept in {}", backup.display())),
+                            Ok(None) => None,
+                            Err(err) => Some(err.to_string()),
+                        };
+                        self.lap = duration::format(settings.timer.lap);
+                        self.rest_lap = duration::format(settings.timer.rest_lap);
+                        self.rest_loop = duration::format(settings.timer.rest_loop);
+                        self.settings = settings;
+                        self.app_state = State::STEADY;
+                    }
+                }
+            }
+        });
+    }
+
+    /// The settings as filled in on the setting screen, if they parse.
+    fn edited_settings(&self) -> Result<Settings, String> {
+        let parse = |name, text: &str| duration::parse(text).map_err(|err| format!("{}: {}", name, err));
+        let settings = Settings {
+            timer: Config {
+                lap: parse("lap", &self.lap)?,
+                rest_lap: parse("lap rest", &self.rest_lap)?,
+                rest_loop: parse("loop rest", &self.rest_loop)?,
+                laps_per_loop: self.laps_per_loop,
+                auto_start_breaks: self.auto_start_breaks,
+                auto_start_laps: self.auto_start_laps,
+                auto_start_loop: self.auto_start_loop,
+            },
+            sounds: Sounds {
+                volume: self.volume,
+                ticking: self.ticking,
+                ..self.settings.sounds.clone()
+            },
+            ..self.settings.clone()
+        };
+        Ok(settings)
+    }
+}
+
+impl eframe::App for MyApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::CentralPanel::default().show(ctx, |ui: &mut egui::Ui| {
+            match self.app_state {
+                State::STEADY => self.steady(ui),
+                State::SETTING => self.setting(ui),
+                State::STATS => self.statistics(ui),
+                State::RECOVER => self.recover(ui),
+            }
+        });
+    }
+}
+
+fn bar_chart(ui: &mut egui::Ui, per_day: &[(chrono::NaiveDate, u32)]) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(ui.available_width().min(420.0), 140.0), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let visuals = ui.visuals();
+    let most = per_day.iter().map(|(_, count)| *count).max().unwrap_or(0).max(1);
+    let label_height = 14.0;
+    let slot = rect.width() / per_day.len().max(1) as f32;
+    let max_bar = rect.height() - 2.0 * label_height;
+    for (i, (date, count)) in per_day.iter().enumerate() {
+        let x = rect.left() + slot * (i as f32 + 0.5);
+        let bottom = rect.bottom() - label_height;
+        let height = max_bar * *count as f32 / most as f32;
+        let bar = egui::Rect::from_min_max(
+            egui::pos2(x - slot * 0.35, bottom - height),
+            egui::pos2(x + slot * 0.35, bottom),
+        );
+        painter.rect_filled(bar, 2.0, visuals.selection.bg_fill);
+        if *count > 0 {
+            painter.text(bar.center_top(), egui::Align2::CENTER_BOTTOM, count.to_string(), egui::FontId::proportional(10.0), visuals.text_color());
+        }
+        painter.text(egui::pos2(x, bottom), egui::Align2::CENTER_TOP, date.format("%d").to_string(), egui::FontId::proportional(10.0), visuals.weak_text_color());
+    }
+}
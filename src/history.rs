@@ -0,0 +1,123 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::mpsc::Receiver,
+    thread::{self, JoinHandle},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use serde::{Deserialize, Serialize};
+use crate::{paths, timer::{Event, RunState}};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Completed,
+    Skipped,
+    Aborted,
+}
+
+/// One phase as it actually happened. Times are Unix seconds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub phase: RunState,
+    /// Lap within the loop, from 1.
+    pub lap: u8,
+    /// Loop, from 1.
+    #[serde(rename = "loop")]
+    pub cycle: u8,
+    pub started_at: u64,
+    pub ended_at: u64,
+    /// What the phase was meant to last in the end, extends included.
+    pub planned_sec: u64,
+    /// How much of that was added while it ran.
+    #[serde(default)]
+    pub extended_sec: u64,
+    /// Time spent counting down, pauses excluded.
+    pub actual_sec: u64,
+    pub outcome: Outcome,
+}
+
+pub fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs())
+}
+
+/// Append-only JSON Lines file of [`Entry`]s.
+#[derive(Clone, Debug)]
+pub struct History {
+    path: PathBuf,
+}
+
+impl History {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// `$XDG_DATA_HOME/pomodoro_rs/history.jsonl`.
+    pub fn open_default() -> Option<Self> {
+        paths::data_dir().map(|dir| Self::new(dir.join("history.jsonl")))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn append(&self, entry: &Entry) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        // one write per line so a crash can at worst cut off the last entry
+        file.write_all(line.as_bytes())?;
+        file.sync_data()
+    }
+
+    /// Every entry on record, oldest first. Lines that don't parse, such as
+    /// one cut short by a crash, are skipped.
+    pub fn load(&self) -> io::Result<Vec<Entry>> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+        let mut entries = Vec::new();
+        for line in BufReader::new(file).lines() {
+            if let Ok(entry) = serde_json::from_str(&line?) {
+                entries.push(entry);
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// Writes every [`Event::Finished`] to a [`History`] from a thread of its
+/// own. The thread ends once the event stream does, so drop this after the
+/// worker it listens to; dropping waits for the last entries to be written.
+pub struct Recorder {
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Recorder {
+    pub fn spawn(history: History, events: Receiver<Event>) -> Self {
+        let thread = thread::spawn(move || {
+            for event in events {
+                if let Event::Finished(entry) = event
+                    && let Err(err) = history.append(&entry)
+                {
+                    eprintln!("pomodoro: could not write {}: {}", history.path().display(), err);
+                }
+            }
+        });
+        Self { thread: Some(thread) }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
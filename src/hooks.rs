@@ -0,0 +1,310 @@
+//! Shell commands run as the timer moves along, from the `[hooks]` table of
+//! the settings:
+//!
+//! ```toml
+//! [hooks]
+//! timeout_sec = 10
+//! lap_start = "makoctl mode -a do-not-disturb"
+//! lap_end = "makoctl mode -r do-not-disturb"
+//! pause = "playerctl pause"
+//! ```
+//!
+//! Each runs through `sh -c` with `POMODORO_EVENT`, `POMODORO_PHASE`,
+//! `POMODORO_LAP`, `POMODORO_LOOP` and `POMODORO_DURATION` (the phase's
+//! length in seconds) set. Whatever a hook prints on stderr goes to
+//! `hooks.log` in the state directory, along with a line for every run.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+use serde::{Deserialize, Serialize};
+use crate::{
+    history::Entry,
+    paths,
+    timer::{Event, RunState, Status},
+    worker::Handle,
+};
+
+/// The commands to run, each optional.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Hooks {
+    /// A hook still running after this long is killed.
+    pub timeout_sec: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lap_start: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lap_end: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rest_start: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rest_end: Option<String>,
+    /// After the loop rest, once every lap of the loop is done.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub loop_complete: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pause: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resume: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop: Option<String>,
+}
+
+impl Default for Hooks {
+    fn default() -> Self {
+        Self {
+            timeout_sec: 10,
+            lap_start: None,
+            lap_end: None,
+            rest_start: None,
+            rest_end: None,
+            loop_complete: None,
+            pause: None,
+            resume: None,
+            stop: None,
+        }
+    }
+}
+
+impl Hooks {
+    pub fn command(&self, hook: Hook) -> Option<&str> {
+        match hook {
+            Hook::LapStart => &self.lap_start,
+            Hook::LapEnd => &self.lap_end,
+            Hook::RestStart => &self.rest_start,
+            Hook::RestEnd => &self.rest_end,
+            Hook::LoopComplete => &self.loop_complete,
+            Hook::Pause => &self.pause,
+            Hook::Resume => &self.resume,
+            Hook::Stop => &self.stop,
+        }
+        .as_deref()
+    }
+
+    pub fn log_path() -> Option<PathBuf> {
+        paths::state_dir().map(|dir| dir.join("hooks.log"))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hook {
+    LapStart,
+    LapEnd,
+    RestStart,
+    RestEnd,
+    LoopComplete,
+    Pause,
+    Resume,
+    Stop,
+}
+
+impl Hook {
+    /// As in the settings, and `POMODORO_EVENT`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Hook::LapStart => "lap_start",
+            Hook::LapEnd => "lap_end",
+            Hook::RestStart => "rest_start",
+            Hook::RestEnd => "rest_end",
+            Hook::LoopComplete => "loop_complete",
+            Hook::Pause => "pause",
+            Hook::Resume => "resume",
+            Hook::Stop => "stop",
+        }
+    }
+
+    fn started(phase: RunState) -> Self {
+        match phase {
+            RunState::LAP => Hook::LapStart,
+            RunState::RestLap | RunState::RestLoop => Hook::RestStart,
+        }
+    }
+
+    fn ended(phase: RunState) -> Self {
+        match phase {
+            RunState::LAP => Hook::LapEnd,
+            RunState::RestLap | RunState::RestLoop => Hook::RestEnd,
+        }
+    }
+}
+
+/// A hook that is due, with what it gets told about the phase.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fired {
+    pub hook: Hook,
+    pub phase: RunState,
+    pub lap: u8,
+    pub cycle: u8,
+    pub duration_sec: u64,
+}
+
+/// Works out from the timer's events which hooks are due. The events alone
+/// don't tell a resume from a start, or whether the phase after the one that
+/// ended is already counting, so this keeps track of both.
+#[derive(Default)]
+pub struct Transitions {
+    counting: bool,
+    paused: bool,
+    /// The phase about to end ran out during a suspend.
+    slept_through: bool,
+    finished: Option<Entry>,
+}
+
+impl Transitions {
+    /// `status` is the timer as it was left by `event`.
+    pub fn feed(&mut self, event: Event, status: &Status) -> Vec<Fired> {
+        let now = |hook, phase| {
+            let (lap, cycle) = status.position();
+            Fired { hook, phase, lap, cycle, duration_sec: status.planned.as_secs() }
+        };
+        // the phase that just ended has moved on in `status` already
+        let then = |hook, phase, finished: Option<Entry>| match finished.filter(|entry| entry.phase == phase) {
+            Some(entry) => Fired { hook, phase, lap: entry.lap, cycle: entry.cycle, duration_sec: entry.planned_sec },
+            None => Fired { hook, phase, lap: 0, cycle: 0, duration_sec: status.config.duration(phase).as_secs() },
+        };
+        match event {
+            Event::Finished(entry) => {
+                self.finished = Some(entry);
+                vec![]
+            }
+            Event::Started(phase) => {
+                let hook = if self.paused { Hook::Resume } else { Hook::started(phase) };
+                self.counting = true;
+                self.paused = false;
+                vec![now(hook, phase)]
+            }
+            Event::Paused(phase) => {
+                self.counting = false;
+                self.paused = true;
+                vec![now(Hook::Pause, phase)]
+            }
+            Event::Stopped => {
+                self.counting = false;
+                self.paused = false;
+                let phase = self.finished.map_or(RunState::LAP, |entry| entry.phase);
+                vec![then(Hook::Stop, phase, self.finished.take())]
+            }
+            Event::PhaseEnded { ended, next } => {
+                let finished = self.finished.take();
+                let mut fired = vec![then(Hook::ended(ended), ended, finished)];
+                if ended == RunState::RestLoop {
+                    fired.push(then(Hook::LoopComplete, ended, finished));
+                }
+                // the next phase either waits to be started or carries
+                // straight on if this one was counting
+                self.paused = false;
+                if !status.config.auto_start(ended) || std::mem::take(&mut self.slept_through) {
+                    self.counting = false;
+                } else if self.counting {
+                    fired.push(now(Hook::started(next), next));
+                }
+                fired
+            }
+            Event::SleptThrough(_) => {
+                self.slept_through = true;
+                vec![]
+            }
+            Event::Skipped(_) | Event::Warning(..) | Event::Extended(..) | Event::Suspended(_) => vec![],
+        }
+    }
+}
+
+/// Runs the configured hooks for a worker's events, one after the other,
+/// until the worker shuts down.
+pub struct HookRunner {
+    thread: Option<JoinHandle<()>>,
+}
+
+impl HookRunner {
+    /// `log` is where runs and the hooks' stderr go, see [`Hooks::log_path`];
+    /// without one stderr is let through.
+    pub fn spawn(hooks: Hooks, log: Option<PathBuf>, handle: Handle) -> Self {
+        // hooks take their time, so by the time one is done the timer may
+        // well have moved on from the events after it
+        let events = handle.subscribe_with_status();
+        let thread = thread::spawn(move || {
+            let mut transitions = Transitions::default();
+            for (event, status) in events {
+                for fired in transitions.feed(event, &status) {
+                    if let Some(command) = hooks.command(fired.hook) {
+                        run(command, &fired, Duration::from_secs(hooks.timeout_sec), log.as_deref());
+                    }
+                }
+            }
+        });
+        Self { thread: Some(thread) }
+    }
+}
+
+impl Drop for HookRunner {
+    fn drop(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn open_log(path: &Path) -> io::Result<File> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+fn run(command: &str, fired: &Fired, timeout: Duration, log: Option<&Path>) {
+    let mut log = log.and_then(|path| {
+        open_log(path)
+            .map_err(|err| eprintln!("pomodoro: could not open {}: {}", path.display(), err))
+            .ok()
+    });
+    note(&mut log, fired.hook, format!("running {}", command));
+    let stderr = match log.as_ref().map(File::try_clone) {
+        Some(Ok(file)) => Stdio::from(file),
+        _ => Stdio::inherit(),
+    };
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("POMODORO_EVENT", fired.hook.name())
+        .env("POMODORO_PHASE", fired.phase.name())
+        .env("POMODORO_LAP", fired.lap.to_string())
+        .env("POMODORO_LOOP", fired.cycle.to_string())
+        .env("POMODORO_DURATION", fired.duration_sec.to_string())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(stderr)
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(err) => return note(&mut log, fired.hook, format!("could not start: {}", err)),
+    };
+    let started = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => return,
+            Ok(Some(status)) => return note(&mut log, fired.hook, format!("failed, {}", status)),
+            Ok(None) if started.elapsed() >= timeout => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return note(&mut log, fired.hook, format!("killed after {}s", timeout.as_secs()));
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(50)),
+            Err(err) => return note(&mut log, fired.hook, format!("could not wait: {}", err)),
+        }
+    }
+}
+
+fn note(log: &mut Option<File>, hook: Hook, message: String) {
+    let line = format!("{} {}: {}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), hook.name(), message);
+    match log {
+        Some(file) => {
+            let _ = writeln!(file, "{}", line);
+        }
+        None => eprintln!("pomodoro: {}", line),
+    }
+}
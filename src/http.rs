@@ -0,0 +1,234 @@
+//! The HTTP API, for dashboards and anything else that would rather not
+//! speak the control socket. It only ever listens on localhost.
+//!
+//! ```text
+//! GET  /status                  {"ok":true,"status":{...}}
+//! POST /start /pause /resume /stop /skip
+//! PUT  /config                  {"lap":"50m","rest_lap":"10m"}
+//! GET  /events                  WebSocket, one JSON message per push
+//! ```
+//!
+//! Answers are the control socket's [`Response`], with `409 Conflict` for a
+//! refused command. The WebSocket pushes `{"event":"phase_changed",...}`
+//! whenever a phase ends and `{"event":"tick","status":{...}}` every second
+//! while counting down as well as after every other change.
+//!
+//! Listening on localhost doesn't keep out the web pages the user has open,
+//! so anything with a `Host` other than `localhost` or `127.0.0.1` on our
+//! port, as DNS rebinding would send, or with someone else's `Origin` gets
+//! `403 Forbidden`.
+
+use std::{
+    io,
+    net::{Ipv4Addr, SocketAddr},
+    sync::{Arc, atomic::{AtomicBool, Ordering}, mpsc::RecvTimeoutError},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+use serde::{Deserialize, Serialize};
+use tiny_http::{Header, Method, StatusCode};
+use tungstenite::{Message, WebSocket, handshake::derive_accept_key, protocol::Role};
+use crate::{
+    ipc::{Report, Request, Response, handle_request},
+    timer::{Event, RunState},
+    worker::Handle,
+};
+
+/// What `PUT /config` takes; fields left out keep their value.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigChange {
+    #[serde(default, with = "crate::duration::human::option", alias = "lap_dur_min")]
+    lap: Option<Duration>,
+    #[serde(default, with = "crate::duration::human::option", alias = "rest_lap_min")]
+    rest_lap: Option<Duration>,
+    #[serde(default, with = "crate::duration::human::option", alias = "rest_loop_min")]
+    rest_loop: Option<Duration>,
+    laps_per_loop: Option<u8>,
+}
+
+/// What the WebSocket pushes.
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Push {
+    PhaseChanged { ended: RunState, next: RunState },
+    Tick { status: Report },
+}
+
+/// Serves a worker over HTTP on `127.0.0.1`. Dropping it stops listening;
+/// open WebSockets are closed within a moment.
+pub struct Server {
+    server: Arc<tiny_http::Server>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Server {
+    /// Port 0 picks a free one, see [`Server::addr`].
+    pub fn bind(port: u16, handle: Handle) -> io::Result<Self> {
+        let server = tiny_http::Server::http((Ipv4Addr::LOCALHOST, port)).map_err(io::Error::other)?;
+        let server = Arc::new(server);
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let server = Arc::clone(&server);
+            let stop = Arc::clone(&stop);
+            let port = server.server_addr().to_ip().map_or(port, |addr| addr.port());
+            thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    serve(request, port, &handle, &stop);
+                }
+            })
+        };
+        Ok(Self {
+            server,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn addr(&self) -> Option<SocketAddr> {
+        self.server.server_addr().to_ip()
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.server.unblock();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn serve(mut request: tiny_http::Request, port: u16, handle: &Handle, stop: &Arc<AtomicBool>) {
+    let route = match (request.method(), request.url()) {
+        _ if !from_here(&request, port) => Err((StatusCode(403), "only for programs on this machine".to_owned())),
+        (Method::Get, "/events") => return upgrade(request, handle, stop),
+        (Method::Get, "/status") => Ok(Request::Status),
+        (Method::Post, "/start") => Ok(Request::Start),
+        (Method::Post, "/pause") => Ok(Request::Pause),
+        (Method::Post, "/resume") => Ok(Request::Resume),
+        (Method::Post, "/stop") => Ok(Request::Stop),
+        (Method::Post, "/skip") => Ok(Request::Skip),
+        (Method::Put, "/config") => config_change(&mut request),
+        (_, "/events" | "/status" | "/start" | "/pause" | "/resume" | "/stop" | "/skip" | "/config") => {
+            Err((StatusCode(405), "method not allowed".to_owned()))
+        }
+        _ => Err((StatusCode(404), "not found".to_owned())),
+    };
+    let (code, response) = match route {
+        Ok(command) => {
+            let response = handle_request(handle, command);
+            (StatusCode(if response.ok { 200 } else { 409 }), response)
+        }
+        Err((code, message)) => (code, Response::error(message)),
+    };
+    let body = serde_json::to_string(&response).unwrap_or_default();
+    let _ = request.respond(
+        tiny_http::Response::from_string(body)
+            .with_status_code(code)
+            .with_header(header("Content-Type", "application/json")),
+    );
+}
+
+fn config_change(request: &mut tiny_http::Request) -> Result<Request, (StatusCode, String)> {
+    let mut body = String::new();
+    request
+        .as_reader()
+        .read_to_string(&mut body)
+        .map_err(|err| (StatusCode(400), err.to_string()))?;
+    let change: ConfigChange =
+        serde_json::from_str(&body).map_err(|err| (StatusCode(400), format!("bad request: {}", err)))?;
+    Ok(Request::SetConfig {
+        lap: change.lap,
+        rest_lap: change.rest_lap,
+        rest_loop: change.rest_loop,
+        laps_per_loop: change.laps_per_loop,
+    })
+}
+
+/// Whether `request` was sent to us by name, and if it says which page sent
+/// it, by none but our own.
+fn from_here(request: &tiny_http::Request, port: u16) -> bool {
+    let host = header_value(request, "Host").is_some_and(|host| is_local(host, port));
+    let origin = header_value(request, "Origin")
+        .is_none_or(|origin| origin.strip_prefix("http://").is_some_and(|host| is_local(host, port)));
+    host && origin
+}
+
+/// `localhost:PORT` or `127.0.0.1:PORT`; without a port only if ours is 80.
+fn is_local(host: &str, port: u16) -> bool {
+    let host = host.to_ascii_lowercase();
+    let (name, given) = match host.rsplit_once(':') {
+        Some((name, given)) => (name, given.parse().ok()),
+        None => (host.as_str(), Some(80)),
+    };
+    matches!(name, "localhost" | "127.0.0.1") && given == Some(port)
+}
+
+fn header_value<'a>(request: &'a tiny_http::Request, field: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv(field))
+        .map(|header| header.value.as_str())
+}
+
+fn header(field: &str, value: &str) -> Header {
+    Header::from_bytes(field.as_bytes(), value.as_bytes()).unwrap()
+}
+
+fn upgrade(request: tiny_http::Request, handle: &Handle, stop: &Arc<AtomicBool>) {
+    let key = header_value(&request, "Sec-WebSocket-Key").map(|key| derive_accept_key(key.as_bytes()));
+    let Some(accept) = key else {
+        let body = serde_json::to_string(&Response::error("expected a WebSocket")).unwrap_or_default();
+        let _ = request.respond(tiny_http::Response::from_string(body).with_status_code(StatusCode(400)));
+        return;
+    };
+    let response = tiny_http::Response::empty(StatusCode(101))
+        .with_header(header("Upgrade", "websocket"))
+        .with_header(header("Connection", "Upgrade"))
+        .with_header(header("Sec-WebSocket-Accept", &accept));
+    let stream = request.upgrade("websocket", response);
+    let socket = WebSocket::from_raw_socket(stream, Role::Server, None);
+    let handle = handle.clone();
+    let stop = Arc::clone(stop);
+    thread::spawn(move || push(socket, &handle, &stop));
+}
+
+/// Feeds one WebSocket until it goes away, the worker shuts down or the
+/// server is dropped.
+fn push<S: io::Read + io::Write>(mut socket: WebSocket<S>, handle: &Handle, stop: &AtomicBool) {
+    let events = handle.subscribe();
+    let mut shown = None;
+    while !stop.load(Ordering::Relaxed) {
+        let mut pushes = Vec::new();
+        match events.recv_timeout(Duration::from_millis(200)) {
+            Ok(event) => {
+                for event in std::iter::once(event).chain(events.try_iter()) {
+                    if let Event::PhaseEnded { ended, next } = event {
+                        pushes.push(Push::PhaseChanged { ended, next });
+                    }
+                }
+                // something changed, whether or not the countdown did
+                shown = None;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        let status = handle.status();
+        if shown != Some(status.remaining_sec()) {
+            shown = Some(status.remaining_sec());
+            pushes.push(Push::Tick { status: status.into() });
+        }
+        for message in pushes {
+            let text = serde_json::to_string(&message).unwrap_or_default();
+            if socket.send(Message::text(text)).is_err() {
+                return;
+            }
+        }
+    }
+    let _ = socket.close(None);
+    let _ = socket.flush();
+}
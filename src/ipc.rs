@@ -0,0 +1,256 @@
+//! The control socket: one JSON object per line each way, a [`Request`] from
+//! the client answered by a [`Response`].
+//!
+//! ```text
+//! {"cmd":"pause"}
+//! {"ok":true,"status":{"phase":"lap","running":true,"paused":true,...}}
+//! {"cmd":"extend","seconds":300}
+//! {"ok":true,"status":{"phase":"lap","running":true,"paused":true,...}}
+//! {"cmd":"set-config","lap":"50m","rest_lap":"10m"}
+//! {"ok":false,"error":"lap must be between 1s and 24h, got 0s"}
+//! ```
+
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    sync::{Arc, atomic::{AtomicBool, Ordering}},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+use serde::{Deserialize, Serialize};
+use crate::{
+    settings::{MAX_DURATION, Settings},
+    timer::{Config, RunState, Status},
+    worker::{Command, Handle},
+};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "kebab-case")]
+pub enum Request {
+    Start,
+    Pause,
+    Resume,
+    Stop,
+    Skip,
+    /// Adds time to the current phase.
+    Extend { seconds: u64 },
+    Status,
+    /// Changes only the fields given, for the running timer; the settings
+    /// file is left alone.
+    /// Durations as in the settings file, so a plain number is minutes.
+    SetConfig {
+        #[serde(default, with = "crate::duration::human::option", alias = "lap_dur_min")]
+        lap: Option<Duration>,
+        #[serde(default, with = "crate::duration::human::option", alias = "rest_lap_min")]
+        rest_lap: Option<Duration>,
+        #[serde(default, with = "crate::duration::human::option", alias = "rest_loop_min")]
+        rest_loop: Option<Duration>,
+        laps_per_loop: Option<u8>,
+    },
+}
+
+/// The timer as reported over the socket.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Report {
+    pub phase: RunState,
+    pub label: String,
+    pub running: bool,
+    pub paused: bool,
+    pub remaining_sec: u32,
+    pub lap: u8,
+    pub laps_per_loop: u8,
+    #[serde(rename = "loop")]
+    pub cycle: u8,
+    /// Length of the current phase, extends included.
+    #[serde(default)]
+    pub planned_sec: u32,
+    pub config: Config,
+}
+
+impl From<Status> for Report {
+    fn from(status: Status) -> Self {
+        Self {
+            phase: status.run_state,
+            label: status.label.clone(),
+            running: status.running,
+            paused: status.pause,
+            remaining_sec: status.remaining_sec(),
+            lap: status.cur_lap,
+            laps_per_loop: status.laps_per_loop,
+            planned_sec: status.planned.as_secs() as u32,
+            cycle: status.cur_loop,
+            config: status.config,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<Report>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Response {
+    fn ok(status: Status) -> Self {
+        Self {
+            ok: true,
+            status: Some(status.into()),
+            error: None,
+        }
+    }
+
+    pub(crate) fn error(message: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            status: None,
+            error: Some(message.into()),
+        }
+    }
+}
+
+/// Carries out `request` on the timer behind `handle`. Commands that make no
+/// sense in the current state, like pausing a paused timer, are refused
+/// rather than ignored so scripts can tell.
+pub fn handle_request(handle: &Handle, request: Request) -> Response {
+    let status = handle.status();
+    let command = match request {
+        Request::Status => return Response::ok(status),
+        Request::Start if status.running => return Response::error("already running"),
+        Request::Start => Command::Start,
+        Request::Pause if !status.running || status.pause => return Response::error("not counting down"),
+        Request::Pause => Command::Pause,
+        Request::Resume if !status.running => return Response::error("nothing to resume"),
+        Request::Resume if !status.pause => return Response::error("not paused"),
+        Request::Resume => Command::Resume,
+        Request::Stop | Request::Skip | Request::Extend { .. } if !status.running => {
+            return Response::error("not running");
+        }
+        Request::Stop => Command::Stop,
+        Request::Skip => Command::Skip,
+        Request::Extend { seconds } => {
+            let by = Duration::from_secs(seconds);
+            if status.planned.checked_add(by).is_none_or(|planned| planned > MAX_DURATION) {
+                return Response::error(format!(
+                    "a phase can't be longer than {}",
+                    crate::duration::format(MAX_DURATION)
+                ));
+            }
+            Command::Extend(by)
+        }
+        Request::SetConfig { lap, rest_lap, rest_loop, laps_per_loop } => {
+            let mut config = status.config;
+            config.lap = lap.unwrap_or(config.lap);
+            config.rest_lap = rest_lap.unwrap_or(config.rest_lap);
+            config.rest_loop = rest_loop.unwrap_or(config.rest_loop);
+            config.laps_per_loop = laps_per_loop.unwrap_or(config.laps_per_loop);
+            let settings = Settings { timer: config, ..Settings::default() };
+            if let Err(err) = settings.validate() {
+                return Response::error(err.to_string());
+            }
+            Command::SetConfig(config)
+        }
+    };
+    match handle.call(command) {
+        Some(status) => Response::ok(status),
+        None => Response::error("timer is shutting down"),
+    }
+}
+
+/// Listens on the control socket and answers requests against a worker, one
+/// thread per connection. Dropping it stops listening and removes the socket.
+pub struct Server {
+    path: PathBuf,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Server {
+    /// Fails with [`io::ErrorKind::AddrInUse`] if another instance already
+    /// answers on `path`. A socket left behind by one that died is replaced.
+    pub fn bind(path: &Path, handle: Handle) -> io::Result<Self> {
+        if path.exists() {
+            if answers(path) {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    format!("another timer is already listening on {}", path.display()),
+                ));
+            }
+            fs::remove_file(path)?;
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let listener = UnixListener::bind(path)?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopping = Arc::clone(&stop);
+        let thread = thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stopping.load(Ordering::Relaxed) {
+                    break;
+                }
+                let Ok(stream) = stream else { continue };
+                let handle = handle.clone();
+                thread::spawn(move || {
+                    let _ = serve(stream, &handle);
+                });
+            }
+        });
+        Ok(Self {
+            path: path.to_owned(),
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        // wake the accept loop up so it notices
+        let _ = UnixStream::connect(&self.path);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn serve(stream: UnixStream, handle: &Handle) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str(&line) {
+            Ok(request) => handle_request(handle, request),
+            Err(err) => Response::error(format!("bad request: {}", err)),
+        };
+        let mut reply = serde_json::to_string(&response)?;
+        reply.push('\n');
+        writer.write_all(reply.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Whether a timer is listening on `path`, as opposed to nothing or a socket
+/// left behind by one that died.
+pub fn answers(path: &Path) -> bool {
+    UnixStream::connect(path).is_ok()
+}
+
+/// Sends one request to whoever listens on `path` and waits for the answer.
+pub fn request(path: &Path, request: &Request) -> io::Result<Response> {
+    let mut stream = UnixStream::connect(path)?;
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    Ok(serde_json::from_str(&reply)?)
+}
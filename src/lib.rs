@@ -0,0 +1,19 @@
+pub mod checkpoint;
+pub mod clock;
+#[cfg(feature = "dbus")]
+pub mod dbus;
+pub mod duration;
+pub mod history;
+pub mod hooks;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod ipc;
+pub mod notify;
+pub mod paths;
+pub mod services;
+pub mod settings;
+pub mod sound;
+pub mod stats;
+pub mod statusbar;
+pub mod timer;
+pub mod worker;
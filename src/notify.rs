@@ -0,0 +1,398 @@
+//! Desktop notifications for the ends of phases, each of which can be
+//! reworded or turned off under `[notifications]` in the settings:
+//!
+//! ```toml
+//! [notifications.break_over]
+//! body = "Back to it"
+//! urgency = "critical"
+//! timeout_ms = 5000
+//!
+//! [notifications.loop_complete]
+//! enabled = false
+//! ```
+//!
+//! The ones asking for a rest come with buttons to start it, skip it or be
+//! reminded again in five minutes.
+
+use std::{
+    fmt::Write,
+    sync::{Arc, Mutex, mpsc::{self, RecvTimeoutError}},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+use notify_rust::{Hint, Notification, Timeout, Urgency as NotifyUrgency};
+use serde::{Deserialize, Serialize};
+use crate::{
+    timer::{Event, RunState, Status},
+    worker::{Command, Handle},
+};
+
+/// Which transition a notification is about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// A lap ended and a lap rest is up next.
+    LapEnd,
+    /// The last lap of the loop ended and the loop rest is up next.
+    LongBreak,
+    /// A lap rest ended and the next lap has started.
+    BreakOver,
+    /// The loop rest ended and the next loop has started.
+    LoopComplete,
+    /// This much is left of the phase, see [`Warnings`](crate::timer::Warnings).
+    Warning(RunState, Duration),
+}
+
+impl Kind {
+    /// What the timer's `PhaseEnded { ended, next }` calls for.
+    pub fn of(ended: RunState, next: RunState) -> Self {
+        match (ended, next) {
+            (RunState::LAP, RunState::RestLoop) => Kind::LongBreak,
+            (RunState::LAP, _) => Kind::LapEnd,
+            (RunState::RestLoop, _) => Kind::LoopComplete,
+            (RunState::RestLap, _) => Kind::BreakOver,
+        }
+    }
+
+    fn body(&self) -> String {
+        match self {
+            Kind::LapEnd => "Time out! Please check your tomato!".to_owned(),
+            Kind::LongBreak => "That was the last lap of the loop, time for a long rest!".to_owned(),
+            Kind::BreakOver => "Rest is over, back to work!".to_owned(),
+            Kind::LoopComplete => "Loop complete! Starting over from the first lap.".to_owned(),
+            Kind::Warning(RunState::LAP, left) => format!("{} left in this lap, time to wrap up", describe(*left)),
+            Kind::Warning(_, left) => format!("{} of rest left", describe(*left)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Urgency {
+    Low,
+    Normal,
+    Critical,
+}
+
+/// How one kind of notification looks. Anything left out keeps the built-in
+/// text, and the notification stays up until dismissed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Notice {
+    pub enabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// An icon name from the theme or a path to an image.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub urgency: Option<Urgency>,
+    /// 0 keeps it up until dismissed, or [`ACTIONS_TIMEOUT`] for ones with
+    /// buttons.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u32>,
+}
+
+impl Default for Notice {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            summary: None,
+            body: None,
+            icon: None,
+            urgency: None,
+            timeout_ms: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Notifications {
+    pub lap_end: Notice,
+    pub long_break: Notice,
+    pub break_over: Notice,
+    pub loop_complete: Notice,
+    /// A custom body can say how long is left with `{left}`.
+    pub warning: Notice,
+}
+
+impl Notifications {
+    pub fn get(&self, kind: Kind) -> &Notice {
+        match kind {
+            Kind::LapEnd => &self.lap_end,
+            Kind::LongBreak => &self.long_break,
+            Kind::BreakOver => &self.break_over,
+            Kind::LoopComplete => &self.loop_complete,
+            Kind::Warning(..) => &self.warning,
+        }
+    }
+
+    /// The notification for `kind` as configured, `None` if it is turned off.
+    pub fn message(&self, kind: Kind) -> Option<Message> {
+        let notice = self.get(kind);
+        if !notice.enabled {
+            return None;
+        }
+        Some(Message {
+            kind,
+            summary: notice.summary.clone().unwrap_or_else(|| "Pomodoro".to_owned()),
+            body: match (&notice.body, kind) {
+                (Some(body), Kind::Warning(_, left)) => body.replace("{left}", &describe(left)),
+                (Some(body), _) => body.clone(),
+                (None, _) => kind.body(),
+            },
+            icon: notice.icon.clone(),
+            urgency: notice.urgency,
+            timeout_ms: notice.timeout_ms.unwrap_or(0),
+            actions: match kind {
+                Kind::LapEnd | Kind::LongBreak => vec![Action::StartBreak, Action::Snooze, Action::SkipBreak],
+                Kind::BreakOver | Kind::LoopComplete | Kind::Warning(..) => vec![],
+            },
+        })
+    }
+}
+
+/// A button on a notification.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    StartBreak,
+    /// Ask again in [`SNOOZE`] if the rest still hasn't started by then.
+    Snooze,
+    SkipBreak,
+}
+
+/// How long [`Action::Snooze`] puts the reminder off.
+pub const SNOOZE: Duration = Duration::from_secs(5 * 60);
+
+/// The longest a notification with buttons stays up, however long it was
+/// asked to, since something has to wait for them to be clicked.
+pub const ACTIONS_TIMEOUT: Duration = SNOOZE;
+
+impl Action {
+    pub fn id(&self) -> &'static str {
+        match self {
+            Action::StartBreak => "start-break",
+            Action::Snooze => "snooze",
+            Action::SkipBreak => "skip-break",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::StartBreak => "Start break",
+            Action::Snooze => "Snooze 5 min",
+            Action::SkipBreak => "Skip break",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        [Action::StartBreak, Action::Snooze, Action::SkipBreak]
+            .into_iter()
+            .find(|action| action.id() == id)
+    }
+}
+
+/// A notification ready to be shown.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Message {
+    pub kind: Kind,
+    pub summary: String,
+    pub body: String,
+    pub icon: Option<String>,
+    pub urgency: Option<Urgency>,
+    /// 0 keeps it up until dismissed, see [`Notice::timeout_ms`].
+    pub timeout_ms: u32,
+    pub actions: Vec<Action>,
+}
+
+/// Called with whichever action the user picks, if any.
+pub type OnAction = Box<dyn FnOnce(Action) + Send>;
+
+/// Somewhere to show notifications.
+pub trait Notifier: Send + Sync {
+    /// Shows `message` without waiting for the user. `on_action` may be
+    /// called later, from any thread.
+    fn show(&self, message: Message, on_action: OnAction);
+}
+
+/// The desktop's notification daemon. Notifications with buttons go away
+/// after [`ACTIONS_TIMEOUT`] at the latest.
+pub struct Desktop;
+
+impl Notifier for Desktop {
+    fn show(&self, message: Message, on_action: OnAction) {
+        let mut notification = Notification::new();
+        notification.summary(&message.summary).body(&message.body).appname("pomodoro");
+        if let Some(icon) = &message.icon {
+            notification.icon(icon);
+        }
+        if let Some(urgency) = message.urgency {
+            notification.urgency(match urgency {
+                Urgency::Low => NotifyUrgency::Low,
+                Urgency::Normal => NotifyUrgency::Normal,
+                Urgency::Critical => NotifyUrgency::Critical,
+            });
+        }
+        let longest = ACTIONS_TIMEOUT.as_millis() as u32;
+        match (message.timeout_ms, message.actions.is_empty()) {
+            (0, true) => notification.hint(Hint::Resident(true)).timeout(Timeout::Never),
+            (0, false) => notification.timeout(Timeout::Milliseconds(longest)),
+            (ms, true) => notification.timeout(Timeout::Milliseconds(ms)),
+            (ms, false) => notification.timeout(Timeout::Milliseconds(ms.min(longest))),
+        };
+        for action in &message.actions {
+            notification.action(action.id(), action.label());
+        }
+        if message.actions.is_empty() {
+            let _ = notification.show();
+            return;
+        }
+        // waiting for a click blocks until the notification goes away
+        thread::spawn(move || {
+            if let Ok(handle) = notification.show() {
+                handle.wait_for_action(|id| {
+                    if let Some(action) = Action::from_id(id) {
+                        on_action(action);
+                    }
+                });
+            }
+        });
+    }
+}
+
+/// Keeps notifications in memory instead of showing them, for tests.
+#[derive(Default)]
+pub struct Mock {
+    shown: Mutex<Vec<(Message, Option<OnAction>)>>,
+}
+
+impl Mock {
+    /// Everything shown so far, oldest first.
+    pub fn messages(&self) -> Vec<Message> {
+        self.shown.lock().unwrap().iter().map(|(message, _)| message.clone()).collect()
+    }
+
+    /// Acts as if the user picked `action` on the `index`th notification.
+    /// False if it doesn't offer that action or was already acted on.
+    pub fn click(&self, index: usize, action: Action) -> bool {
+        let on_action = match self.shown.lock().unwrap().get_mut(index) {
+            Some((message, on_action)) if message.actions.contains(&action) => on_action.take(),
+            _ => None,
+        };
+        match on_action {
+            Some(on_action) => {
+                on_action(action);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Notifier for Mock {
+    fn show(&self, message: Message, on_action: OnAction) {
+        self.shown.lock().unwrap().push((message, Some(on_action)));
+    }
+}
+
+/// Shows a notification whenever a phase of a worker's timer ends and
+/// carries out the actions picked on them, until the worker shuts down.
+pub struct Announcer {
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Announcer {
+    pub fn spawn(notifications: Notifications, notifier: Arc<dyn Notifier>, handle: Handle) -> Self {
+        let events = handle.subscribe();
+        let thread = thread::spawn(move || {
+            let (tx, actions) = mpsc::channel();
+            // bumped whenever the rest a notification asked about is over,
+            // so buttons clicked on older ones don't act on the one after
+            let mut phase = 0u64;
+            let announce = |kind, phase| {
+                if let Some(mut message) = notifications.message(kind) {
+                    // nothing to decide about a rest that started by itself
+                    if !waiting_for_rest(&handle.status()) {
+                        message.actions.clear();
+                    }
+                    let tx = tx.clone();
+                    notifier.show(message, Box::new(move |action| {
+                        let _ = tx.send((phase, kind, action));
+                    }));
+                }
+            };
+            let mut snoozed: Option<(Instant, Kind)> = None;
+            loop {
+                match events.recv_timeout(Duration::from_millis(200)) {
+                    Ok(Event::PhaseEnded { ended, next }) => {
+                        snoozed = None;
+                        phase += 1;
+                        announce(Kind::of(ended, next), phase);
+                    }
+                    Ok(Event::Warning(state, left)) => announce(Kind::Warning(state, left), phase),
+                    Ok(Event::Started(_) | Event::Stopped) => {
+                        snoozed = None;
+                        phase += 1;
+                    }
+                    Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+                for (shown, kind, action) in actions.try_iter() {
+                    // the user may have moved on in the meantime
+                    if shown != phase || !waiting_for_rest(&handle.status()) {
+                        continue;
+                    }
+                    match action {
+                        Action::StartBreak => handle.send(Command::Resume),
+                        Action::SkipBreak => {
+                            handle.send(Command::Skip);
+                            handle.send(Command::Resume);
+                        }
+                        Action::Snooze => snoozed = Some((Instant::now() + SNOOZE, kind)),
+                    }
+                }
+                if let Some((until, kind)) = snoozed
+                    && Instant::now() >= until
+                {
+                    snoozed = None;
+                    if waiting_for_rest(&handle.status()) {
+                        announce(kind, phase);
+                    }
+                }
+            }
+        });
+        Self { thread: Some(thread) }
+    }
+}
+
+impl Drop for Announcer {
+    fn drop(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// A lap is over and its rest hasn't been started yet.
+fn waiting_for_rest(status: &Status) -> bool {
+    status.running && status.pause && status.run_state != RunState::LAP
+}
+
+/// `left` in words, like "2 minutes" or "1 minute 30 seconds".
+fn describe(left: Duration) -> String {
+    let (min, sec) = (left.as_secs() / 60, left.as_secs() % 60);
+    let plural = |n| if n == 1 { "" } else { "s" };
+    let mut text = String::new();
+    if min > 0 {
+        let _ = write!(text, "{} minute{}", min, plural(min));
+    }
+    if sec > 0 || min == 0 {
+        if !text.is_empty() {
+            text.push(' ');
+        }
+        let _ = write!(text, "{} second{}", sec, plural(sec));
+    }
+    text
+}
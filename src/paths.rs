@@ -0,0 +1,34 @@
+use std::{env, path::PathBuf};
+
+fn xdg_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+    env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(fallback)))
+        .map(|dir| dir.join("pomodoro_rs"))
+}
+
+/// `$XDG_CONFIG_HOME/pomodoro_rs`, usually `~/.config/pomodoro_rs`.
+pub fn config_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
+
+/// `$XDG_DATA_HOME/pomodoro_rs`, usually `~/.local/share/pomodoro_rs`.
+pub fn data_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_DATA_HOME", ".local/share")
+}
+
+/// `$XDG_STATE_HOME/pomodoro_rs`, usually `~/.local/state/pomodoro_rs`.
+pub fn state_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_STATE_HOME", ".local/state")
+}
+
+/// Where the control socket lives: `$XDG_RUNTIME_DIR/pomodoro_rs.sock`, or
+/// the state directory on systems without a runtime directory.
+pub fn socket_path() -> Option<PathBuf> {
+    env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .map(|dir| dir.join("pomodoro_rs.sock"))
+        .or_else(|| state_dir().map(|dir| dir.join("control.sock")))
+}
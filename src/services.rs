@@ -0,0 +1,144 @@
+//! Everything a frontend runs next to its timer, wired up the same way
+//! whichever frontend it is.
+
+use std::{io, path::{Path, PathBuf}, sync::Arc};
+#[cfg(feature = "http")]
+use std::net::SocketAddr;
+use crate::{
+    checkpoint::{Checkpoint, Checkpointer},
+    history::{History, Recorder},
+    hooks::{HookRunner, Hooks},
+    ipc,
+    notify::{Announcer, Desktop},
+    settings::Settings,
+    sound::{self, Player},
+    worker::{Command, Worker},
+};
+#[cfg(feature = "dbus")]
+use crate::dbus;
+#[cfg(feature = "http")]
+use crate::http;
+
+/// A worker and the threads hanging off it: the history, hooks,
+/// notifications and sounds from the start, the session checkpoint and the
+/// ways in for other programs once asked for. Dropping it shuts them down in
+/// an order that lets every one of them see the worker's last events.
+pub struct Services {
+    // the ways in go first, before the worker they drive
+    server: Option<ipc::Server>,
+    #[cfg(feature = "dbus")]
+    bus: Option<dbus::Service>,
+    #[cfg(feature = "http")]
+    http: Option<http::Server>,
+    worker: Worker,
+    // dropped after the worker so they get to write its last words
+    _recorder: Option<Recorder>,
+    checkpointer: Option<Checkpointer>,
+    _hooks: HookRunner,
+    _announcer: Announcer,
+    player: Player,
+    history: Option<History>,
+    sound_error: Option<String>,
+    checkpoint_path: Option<PathBuf>,
+    /// What the last run left in the checkpoint, until it is settled.
+    left_over: Option<Checkpoint>,
+}
+
+impl Services {
+    /// Sets up what `settings` asks for around `worker`.
+    pub fn spawn(settings: &Settings, worker: Worker) -> Self {
+        let history = History::open_default();
+        let (output, sound_error) = sound::output_or_null();
+        Self {
+            server: None,
+            #[cfg(feature = "dbus")]
+            bus: None,
+            #[cfg(feature = "http")]
+            http: None,
+            _recorder: history.clone().map(|history| Recorder::spawn(history, worker.subscribe())),
+            checkpointer: None,
+            _hooks: HookRunner::spawn(settings.hooks.clone(), Hooks::log_path(), worker.handle()),
+            _announcer: Announcer::spawn(settings.notifications.clone(), Arc::new(Desktop), worker.handle()),
+            player: Player::spawn(settings.sounds.clone(), output, worker.handle()),
+            worker,
+            history,
+            sound_error,
+            checkpoint_path: None,
+            left_over: None,
+        }
+    }
+
+    pub fn worker(&self) -> &Worker {
+        &self.worker
+    }
+
+    pub fn player(&self) -> &Player {
+        &self.player
+    }
+
+    /// Where finished phases go, `None` without a data directory.
+    pub fn history(&self) -> Option<&History> {
+        self.history.as_ref()
+    }
+
+    /// Why nothing can be heard, if it can't.
+    pub fn sound_error(&self) -> Option<&str> {
+        self.sound_error.as_deref()
+    }
+
+    /// Answers `pomodoro_rs pause` and friends on `socket`, see
+    /// [`ipc::Server::bind`].
+    pub fn serve(&mut self, socket: &Path) -> io::Result<()> {
+        self.server = Some(ipc::Server::bind(socket, self.worker.handle())?);
+        Ok(())
+    }
+
+    /// Takes the timer's name on the session bus, see [`dbus::Service`].
+    #[cfg(feature = "dbus")]
+    pub fn serve_dbus(&mut self) -> zbus::Result<()> {
+        self.bus = Some(dbus::Service::spawn(self.worker.handle())?);
+        Ok(())
+    }
+
+    /// Serves the HTTP API on `port` of localhost and says where it ended up.
+    #[cfg(feature = "http")]
+    pub fn serve_http(&mut self, port: u16) -> io::Result<Option<SocketAddr>> {
+        let server = http::Server::bind(port, self.worker.handle())?;
+        let addr = server.addr();
+        self.http = Some(server);
+        Ok(addr)
+    }
+
+    /// Takes charge of the session checkpoint, see [`Checkpoint::path`], and
+    /// keeps it in step with the timer. A session the last run left there is
+    /// handed back instead and nothing is saved over it until
+    /// [`Services::restore`] or [`Services::discard`] settles it.
+    pub fn take_checkpoint(&mut self) -> Option<Checkpoint> {
+        self.checkpoint_path = Checkpoint::path();
+        self.left_over = self.checkpoint_path.as_deref().and_then(Checkpoint::load);
+        if self.left_over.is_none() {
+            self.keep_checkpoint();
+        }
+        self.left_over
+    }
+
+    /// Picks the session the last run left back up, paused.
+    pub fn restore(&mut self) {
+        if let Some(checkpoint) = self.left_over.take() {
+            self.worker.send(Command::Restore(checkpoint));
+        }
+        self.keep_checkpoint();
+    }
+
+    /// Throws the session the last run left away, see [`Checkpoint::discard`].
+    pub fn discard(&mut self) {
+        if let (Some(checkpoint), Some(path)) = (self.left_over.take(), &self.checkpoint_path) {
+            checkpoint.discard(path, self.history.as_ref());
+        }
+        self.keep_checkpoint();
+    }
+
+    fn keep_checkpoint(&mut self) {
+        self.checkpointer = self.checkpoint_path.clone().map(|path| Checkpointer::spawn(path, self.worker.handle()));
+    }
+}
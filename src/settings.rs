@@ -0,0 +1,245 @@
+use std::{fmt, fs, io, path::{Path, PathBuf}, time::Duration};
+use serde::{Deserialize, Serialize};
+use crate::{
+    checkpoint::Checkpoint,
+    duration,
+    hooks::Hooks,
+    notify::Notifications,
+    paths,
+    sound::{Player, Sounds},
+    timer::{Config, RunState, Status, Step, Timer, Warnings},
+    worker::{Command, Handle},
+};
+
+/// Bumped whenever the file layout changes in a way older builds can't read.
+/// Version 2 writes durations like `lap = "25m"` instead of `lap_dur_min = 25`;
+/// version 1 files still load and are rewritten on the next save.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// The longest a single phase may be set to.
+pub const MAX_DURATION: Duration = Duration::from_secs(24 * 3600);
+
+/// Everything kept in `config.toml`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub version: u32,
+    pub timer: Config,
+    pub warnings: Warnings,
+    pub hooks: Hooks,
+    pub notifications: Notifications,
+    pub sounds: Sounds,
+    /// Phases to go through instead of the usual laps and rests.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sequence: Vec<Step>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            timer: Config::default(),
+            warnings: Warnings::default(),
+            hooks: Hooks::default(),
+            notifications: Notifications::default(),
+            sounds: Sounds::default(),
+            sequence: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    NoConfigDir,
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Serialize(toml::ser::Error),
+    Version(u32),
+    Invalid(String),
+    /// Put into effect all the same, see [`Settings::apply`].
+    NotSaved(Box<Error>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NoConfigDir => write!(f, "could not find a config directory (is $HOME set?)"),
+            Error::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            Error::Parse(path, err) => write!(f, "{}: {}", path.display(), err),
+            Error::Serialize(err) => write!(f, "could not write settings: {}", err),
+            Error::Version(version) => write!(
+                f,
+                "config version {} is newer than this build understands ({})",
+                version, SCHEMA_VERSION
+            ),
+            Error::Invalid(reason) => write!(f, "{}", reason),
+            Error::NotSaved(err) => write!(f, "not saved: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Settings {
+    pub fn path() -> Option<PathBuf> {
+        paths::config_dir().map(|dir| dir.join("config.toml"))
+    }
+
+    /// Reads the settings file. A missing file is not an error and gives the
+    /// defaults.
+    pub fn load() -> Result<Self, Error> {
+        Self::load_from(&Self::path().ok_or(Error::NoConfigDir)?)
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self, Error> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(Error::Io(path.to_owned(), err)),
+        };
+        let settings: Self = toml::from_str(&text).map_err(|err| Error::Parse(path.to_owned(), err))?;
+        if settings.version > SCHEMA_VERSION {
+            return Err(Error::Version(settings.version));
+        }
+        settings.validate()?;
+        Ok(Self {
+            version: SCHEMA_VERSION,
+            ..settings
+        })
+    }
+
+    /// Like [`Settings::load`], but never fails: whatever went wrong is handed
+    /// back next to the defaults so the UI can show it.
+    pub fn load_or_default() -> (Self, Option<Error>) {
+        match Self::load() {
+            Ok(settings) => (settings, None),
+            Err(err) => (Self::default(), Some(err)),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        self.save_to(&Self::path().ok_or(Error::NoConfigDir)?)
+    }
+
+    /// Writes to a temporary file first so a crash can't leave half a config
+    /// behind.
+    pub fn save_to(&self, path: &Path) -> Result<(), Error> {
+        self.validate()?;
+        let text = toml::to_string_pretty(self).map_err(Error::Serialize)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| Error::Io(dir.to_owned(), err))?;
+        }
+        let tmp = path.with_extension("toml.tmp");
+        fs::write(&tmp, text).map_err(|err| Error::Io(tmp.clone(), err))?;
+        fs::rename(&tmp, path).map_err(|err| Error::Io(path.to_owned(), err))
+    }
+
+    /// Moves the settings file to `config.toml.bak` next to it. Meant for a
+    /// file that failed to load, before saving over it, so a typo doesn't
+    /// cost everything else in it.
+    pub fn back_up() -> Result<Option<PathBuf>, Error> {
+        Self::back_up_at(&Self::path().ok_or(Error::NoConfigDir)?)
+    }
+
+    /// Gives where the file went, or `None` if there was no file.
+    pub fn back_up_at(path: &Path) -> Result<Option<PathBuf>, Error> {
+        let backup = path.with_extension("toml.bak");
+        match fs::rename(path, &backup) {
+            Ok(()) => Ok(Some(backup)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(Error::Io(path.to_owned(), err)),
+        }
+    }
+
+    /// What the setting screens do on confirm: checks the settings, hands
+    /// the durations and sound settings to the running timer and `player`,
+    /// then saves them. With `broken`, the `config.toml` that failed to load
+    /// is backed up first rather than saved over; gives where it went.
+    ///
+    /// Settings that don't validate change nothing. Ones that merely can't
+    /// be saved are in use anyway, since an unwritable config shouldn't stop
+    /// the timer, and come back as [`Error::NotSaved`].
+    pub fn apply(&self, broken: bool, handle: &Handle, player: &Player) -> Result<Option<PathBuf>, Error> {
+        self.apply_to(Self::path().as_deref(), broken, handle, player)
+    }
+
+    /// Like [`Settings::apply`], saving to `path`, if there is one.
+    pub fn apply_to(
+        &self,
+        path: Option<&Path>,
+        broken: bool,
+        handle: &Handle,
+        player: &Player,
+    ) -> Result<Option<PathBuf>, Error> {
+        self.validate()?;
+        handle.send(Command::SetConfig(self.timer));
+        player.set_volume(self.sounds.volume);
+        player.set_ticking(self.sounds.ticking);
+        let saved = path.ok_or(Error::NoConfigDir).and_then(|path| {
+            let backup = if broken { Self::back_up_at(path)? } else { None };
+            self.save_to(path).map(|()| backup)
+        });
+        saved.map_err(|err| Error::NotSaved(Box::new(err)))
+    }
+
+    /// A timer set up the way these settings say.
+    pub fn new_timer(&self) -> Timer {
+        let mut timer = Timer::new(self.timer);
+        timer.set_warnings(self.warnings.clone());
+        timer.set_sequence(self.sequence.clone());
+        timer
+    }
+
+    /// What restoring `checkpoint` into a timer with these settings would
+    /// bring back, to show before deciding about it.
+    pub fn preview(&self, checkpoint: &Checkpoint) -> Status {
+        let mut timer = self.new_timer();
+        timer.restore(checkpoint);
+        timer.status()
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        let timer = &self.timer;
+        for (name, length) in [("lap", timer.lap), ("rest_lap", timer.rest_lap), ("rest_loop", timer.rest_loop)] {
+            if length < Duration::from_secs(1) || length > MAX_DURATION {
+                return Err(Error::Invalid(format!(
+                    "{} must be between 1s and {}, got {}",
+                    name,
+                    duration::format(MAX_DURATION),
+                    duration::format(length)
+                )));
+            }
+        }
+        for (i, step) in self.sequence.iter().enumerate() {
+            if step.label.trim().is_empty() {
+                return Err(Error::Invalid(format!("sequence step {} needs a label", i + 1)));
+            }
+            if step.length < Duration::from_secs(1) || step.length > MAX_DURATION {
+                return Err(Error::Invalid(format!(
+                    "sequence step {} ({}) must be between 1s and {}, got {}",
+                    i + 1,
+                    step.label,
+                    duration::format(MAX_DURATION),
+                    duration::format(step.length)
+                )));
+            }
+        }
+        if self.sequence.len() > usize::from(u8::MAX) {
+            return Err(Error::Invalid(format!("a sequence can have at most {} steps", u8::MAX)));
+        }
+        if !self.sequence.is_empty() && !self.sequence.iter().any(|step| step.kind == RunState::LAP) {
+            return Err(Error::Invalid("a sequence needs at least one step of kind \"lap\"".to_owned()));
+        }
+        if timer.laps_per_loop == 0 {
+            return Err(Error::Invalid("laps_per_loop must be at least 1".to_owned()));
+        }
+        if self.warnings.lap_sec.contains(&0) || self.warnings.rest_sec.contains(&0) {
+            return Err(Error::Invalid("warnings must come at least 1 second before the end".to_owned()));
+        }
+        if self.hooks.timeout_sec == 0 {
+            return Err(Error::Invalid("hooks.timeout_sec must be at least 1".to_owned()));
+        }
+        self.sounds.check().map_err(Error::Invalid)?;
+        Ok(())
+    }
+}
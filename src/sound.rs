@@ -0,0 +1,341 @@
+//! Sounds for the ends of phases and the warnings before them, and an
+//! optional tick every second of a lap, from `[sounds]` in the settings:
+//!
+//! ```toml
+//! [sounds]
+//! volume = 60
+//! ticking = true
+//! lap_end = "gong"
+//! rest_end = "/home/me/sounds/back-to-work.ogg"
+//! warning = "off"
+//! ```
+//!
+//! Each sound is one of the bundled ones (bell, chime, gong, ping and tick),
+//! the path of a WAV or OGG file, or "off". Without a sound card, or built
+//! without the `sound` feature, everything goes to [`Null`] instead.
+
+use std::{
+    borrow::Cow,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, atomic::{AtomicBool, AtomicU8, Ordering}, mpsc::RecvTimeoutError},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+use serde::{Deserialize, Serialize};
+use crate::{
+    timer::{Event, RunState},
+    worker::Handle,
+};
+
+/// The sounds that come with the app, by name.
+pub const BUNDLED: [(&str, &[u8]); 5] = [
+    ("bell", include_bytes!("../assets/sounds/bell.wav")),
+    ("chime", include_bytes!("../assets/sounds/chime.wav")),
+    ("gong", include_bytes!("../assets/sounds/gong.wav")),
+    ("ping", include_bytes!("../assets/sounds/ping.wav")),
+    ("tick", include_bytes!("../assets/sounds/tick.wav")),
+];
+
+/// What a sound is played for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cue {
+    LapEnd,
+    RestEnd,
+    /// The loop rest ended.
+    LoopEnd,
+    Warning,
+    /// Every second of a lap, if ticking is on.
+    Tick,
+}
+
+impl Cue {
+    /// What the end of `phase` sounds like.
+    pub fn ended(phase: RunState) -> Self {
+        match phase {
+            RunState::LAP => Cue::LapEnd,
+            RunState::RestLap => Cue::RestEnd,
+            RunState::RestLoop => Cue::LoopEnd,
+        }
+    }
+
+    /// As in the settings.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Cue::LapEnd => "lap_end",
+            Cue::RestEnd => "rest_end",
+            Cue::LoopEnd => "loop_end",
+            Cue::Warning => "warning",
+            Cue::Tick => "tick",
+        }
+    }
+}
+
+/// Where a sound comes from.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Source {
+    Bundled(&'static str),
+    File(PathBuf),
+}
+
+impl Source {
+    /// A name from the settings, `None` for "off" or nothing at all.
+    /// Anything that isn't a bundled sound is taken for a path.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim() {
+            "" | "off" => None,
+            name => Some(match BUNDLED.iter().find(|(bundled, _)| *bundled == name) {
+                Some((bundled, _)) => Source::Bundled(bundled),
+                None => Source::File(PathBuf::from(name)),
+            }),
+        }
+    }
+
+    /// The encoded sound, read from disk if need be.
+    pub fn bytes(&self) -> io::Result<Cow<'static, [u8]>> {
+        match self {
+            Source::Bundled(name) => Ok(Cow::Borrowed(
+                BUNDLED.iter().find(|(bundled, _)| bundled == name).map_or(&[][..], |(_, bytes)| bytes),
+            )),
+            Source::File(path) => fs::read(path).map(Cow::Owned),
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Bundled(name) => write!(f, "{}", name),
+            Source::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Sounds {
+    /// In percent, 0 to 100.
+    pub volume: u8,
+    /// Tick every second while a lap counts down.
+    pub ticking: bool,
+    pub lap_end: String,
+    pub rest_end: String,
+    /// After the loop rest.
+    pub loop_end: String,
+    pub warning: String,
+    pub tick: String,
+}
+
+impl Default for Sounds {
+    fn default() -> Self {
+        Self {
+            volume: 80,
+            ticking: false,
+            lap_end: "bell".to_owned(),
+            rest_end: "chime".to_owned(),
+            loop_end: "gong".to_owned(),
+            warning: "ping".to_owned(),
+            tick: "tick".to_owned(),
+        }
+    }
+}
+
+impl Sounds {
+    pub fn name(&self, cue: Cue) -> &str {
+        match cue {
+            Cue::LapEnd => &self.lap_end,
+            Cue::RestEnd => &self.rest_end,
+            Cue::LoopEnd => &self.loop_end,
+            Cue::Warning => &self.warning,
+            Cue::Tick => &self.tick,
+        }
+    }
+
+    /// The sound for `cue`, `None` if it is turned off.
+    pub fn get(&self, cue: Cue) -> Option<Source> {
+        Source::parse(self.name(cue))
+    }
+
+    /// A name that is neither bundled nor looks like a file is most likely
+    /// a typo, which is better caught here than by silence later.
+    pub fn check(&self) -> Result<(), String> {
+        if self.volume > 100 {
+            return Err(format!("sounds.volume must be at most 100, got {}", self.volume));
+        }
+        for cue in [Cue::LapEnd, Cue::RestEnd, Cue::LoopEnd, Cue::Warning, Cue::Tick] {
+            if let Some(Source::File(path)) = self.get(cue)
+                && path.extension().is_none()
+                && path.parent() == Some(Path::new(""))
+            {
+                let names: Vec<&str> = BUNDLED.iter().map(|(name, _)| *name).collect();
+                return Err(format!(
+                    "sounds.{}: no sound called {:?}, try one of {} or a path",
+                    cue.name(),
+                    self.name(cue),
+                    names.join(", ")
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Somewhere to play sounds.
+pub trait Output: Send + Sync {
+    /// Starts playing `source` at `volume` (0.0 to 1.0) without waiting for
+    /// it to finish.
+    fn play(&self, source: &Source, volume: f32);
+}
+
+/// Plays nothing, for machines without sound.
+pub struct Null;
+
+impl Output for Null {
+    fn play(&self, _: &Source, _: f32) {}
+}
+
+/// Keeps track of what would have been played, for tests.
+#[derive(Default)]
+pub struct Mock {
+    played: Mutex<Vec<(Source, f32)>>,
+}
+
+impl Mock {
+    /// Everything played so far with its volume, oldest first.
+    pub fn played(&self) -> Vec<(Source, f32)> {
+        self.played.lock().unwrap().clone()
+    }
+}
+
+impl Output for Mock {
+    fn play(&self, source: &Source, volume: f32) {
+        self.played.lock().unwrap().push((source.clone(), volume));
+    }
+}
+
+/// The default sound card.
+#[cfg(feature = "sound")]
+pub struct Speakers {
+    tx: std::sync::mpsc::Sender<(Source, f32)>,
+}
+
+#[cfg(feature = "sound")]
+impl Speakers {
+    pub fn open() -> Result<Self, String> {
+        use std::{collections::HashSet, sync::mpsc};
+        let (tx, requests) = mpsc::channel::<(Source, f32)>();
+        let (opened, result) = mpsc::channel();
+        // the output stream can't leave the thread it was opened on
+        thread::spawn(move || {
+            let (_stream, stream) = match rodio::OutputStream::try_default() {
+                Ok(output) => output,
+                Err(err) => {
+                    let _ = opened.send(Err(err.to_string()));
+                    return;
+                }
+            };
+            let _ = opened.send(Ok(()));
+            let mut broken = HashSet::new();
+            for (source, volume) in requests {
+                if let Err(err) = play(&stream, &source, volume) {
+                    // a missing file would otherwise complain every second
+                    if broken.insert(source.clone()) {
+                        eprintln!("pomodoro: could not play {}: {}", source, err);
+                    }
+                }
+            }
+        });
+        result.recv().unwrap_or_else(|_| Err("the sound thread died".to_owned()))?;
+        Ok(Self { tx })
+    }
+}
+
+#[cfg(feature = "sound")]
+impl Output for Speakers {
+    fn play(&self, source: &Source, volume: f32) {
+        let _ = self.tx.send((source.clone(), volume));
+    }
+}
+
+#[cfg(feature = "sound")]
+fn play(stream: &rodio::OutputStreamHandle, source: &Source, volume: f32) -> Result<(), String> {
+    let bytes = source.bytes().map_err(|err| err.to_string())?;
+    let decoder = rodio::Decoder::new(io::Cursor::new(bytes)).map_err(|err| err.to_string())?;
+    let sink = rodio::Sink::try_new(stream).map_err(|err| err.to_string())?;
+    sink.set_volume(volume);
+    sink.append(decoder);
+    sink.detach();
+    Ok(())
+}
+
+/// The sound card if there is one, otherwise [`Null`] along with why not.
+pub fn output_or_null() -> (Arc<dyn Output>, Option<String>) {
+    #[cfg(feature = "sound")]
+    return match Speakers::open() {
+        Ok(speakers) => (Arc::new(speakers), None),
+        Err(err) => (Arc::new(Null), Some(err)),
+    };
+    #[cfg(not(feature = "sound"))]
+    (Arc::new(Null), Some("built without the sound feature".to_owned()))
+}
+
+/// Plays the configured sounds for a worker's events, until the worker
+/// shuts down.
+pub struct Player {
+    volume: Arc<AtomicU8>,
+    ticking: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Player {
+    pub fn spawn(sounds: Sounds, output: Arc<dyn Output>, handle: Handle) -> Self {
+        let volume = Arc::new(AtomicU8::new(sounds.volume));
+        let ticking = Arc::new(AtomicBool::new(sounds.ticking));
+        let events = handle.subscribe();
+        let thread = thread::spawn({
+            let (volume, ticking) = (Arc::clone(&volume), Arc::clone(&ticking));
+            move || {
+                let play = |cue| {
+                    if let Some(source) = sounds.get(cue) {
+                        output.play(&source, f32::from(volume.load(Ordering::Relaxed).min(100)) / 100.0);
+                    }
+                };
+                let mut ticked = None;
+                loop {
+                    match events.recv_timeout(Duration::from_millis(100)) {
+                        Ok(Event::PhaseEnded { ended, .. }) => play(Cue::ended(ended)),
+                        Ok(Event::Warning(..)) => play(Cue::Warning),
+                        Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                    let status = handle.status();
+                    let counting = status.running && !status.pause && status.run_state == RunState::LAP;
+                    if !(counting && ticking.load(Ordering::Relaxed)) {
+                        ticked = None;
+                    } else if ticked != Some(status.remaining_sec()) {
+                        ticked = Some(status.remaining_sec());
+                        play(Cue::Tick);
+                    }
+                }
+            }
+        });
+        Self { volume, ticking, thread: Some(thread) }
+    }
+
+    /// Takes effect from the next sound on.
+    pub fn set_volume(&self, percent: u8) {
+        self.volume.store(percent, Ordering::Relaxed);
+    }
+
+    pub fn set_ticking(&self, ticking: bool) {
+        self.ticking.store(ticking, Ordering::Relaxed);
+    }
+}
+
+impl Drop for Player {
+    fn drop(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::{BTreeMap, BTreeSet};
+use chrono::{Datelike, Days, Local, NaiveDate, TimeZone};
+use crate::{history::{Entry, Outcome}, timer::RunState};
+
+/// Figures for the statistics screen. Only laps count as pomodoros; rests
+/// are left out of everything here.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub completed_today: u32,
+    pub completed_this_week: u32,
+    pub focus_minutes: u64,
+    /// Share of started laps that ran to the end, `None` before the first.
+    pub completion_rate: Option<f32>,
+    /// Most consecutive days with at least one completed lap.
+    pub longest_streak: u32,
+    /// Completed laps per day for the last few days, oldest first.
+    pub per_day: Vec<(NaiveDate, u32)>,
+}
+
+/// The local date an entry ended on.
+pub fn local_date(unix_secs: u64) -> Option<NaiveDate> {
+    Local
+        .timestamp_opt(unix_secs as i64, 0)
+        .earliest()
+        .map(|time| time.date_naive())
+}
+
+impl Stats {
+    pub fn today(entries: &[Entry], days: u64) -> Self {
+        Self::compute(entries, Local::now().date_naive(), days, local_date)
+    }
+
+    /// `to_date` maps an entry's end time to the day it is counted under.
+    pub fn compute(
+        entries: &[Entry],
+        today: NaiveDate,
+        days: u64,
+        to_date: impl Fn(u64) -> Option<NaiveDate>,
+    ) -> Self {
+        let week_start = today
+            .checked_sub_days(Days::new(today.weekday().num_days_from_monday() as u64))
+            .unwrap_or(today);
+        let mut stats = Self::default();
+        let mut started = 0;
+        let mut completed = 0;
+        let mut focus_sec = 0;
+        let mut by_day = BTreeMap::<NaiveDate, u32>::new();
+        for entry in entries.iter().filter(|entry| entry.phase == RunState::LAP) {
+            started += 1;
+            focus_sec += entry.actual_sec;
+            if entry.outcome != Outcome::Completed {
+                continue;
+            }
+            completed += 1;
+            let Some(date) = to_date(entry.ended_at) else {
+                continue;
+            };
+            *by_day.entry(date).or_default() += 1;
+            if date == today {
+                stats.completed_today += 1;
+            }
+            if week_start <= date && date <= today {
+                stats.completed_this_week += 1;
+            }
+        }
+        // rounded down once, so short laps still add up
+        stats.focus_minutes = focus_sec / 60;
+        if started > 0 {
+            stats.completion_rate = Some(completed as f32 / started as f32);
+        }
+        stats.longest_streak = longest_streak(by_day.keys().copied().collect());
+        stats.per_day = (0..days)
+            .rev()
+            .filter_map(|back| today.checked_sub_days(Days::new(back)))
+            .map(|date| (date, by_day.get(&date).copied().unwrap_or(0)))
+            .collect();
+        stats
+    }
+}
+
+fn longest_streak(dates: BTreeSet<NaiveDate>) -> u32 {
+    let mut longest = 0;
+    let mut current = 0;
+    let mut prev: Option<NaiveDate> = None;
+    for date in dates {
+        current = match prev.and_then(|prev| prev.succ_opt()) {
+            Some(next) if next == date => current + 1,
+            _ => 1,
+        };
+        longest = longest.max(current);
+        prev = Some(date);
+    }
+    longest
+}
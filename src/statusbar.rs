@@ -0,0 +1,127 @@
+//! One line about the running timer for status bars: waybar, polybar,
+//! i3blocks or anything else that reads a command's output.
+
+use clap::ValueEnum;
+use serde::Serialize;
+use crate::{duration, ipc::{Report, Response}, timer::RunState};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// The full answer from the timer, `{"ok":true,"status":{...}}`
+    Json,
+    /// A JSON line for a waybar custom module with `return-type: json`
+    Waybar,
+    /// Text with polybar colour tags
+    Polybar,
+    /// Text
+    Plain,
+}
+
+/// What waybar reads from a custom module, one object per line.
+#[derive(Serialize)]
+struct Waybar {
+    text: String,
+    tooltip: String,
+    class: &'static str,
+    percentage: u32,
+}
+
+/// The line for `report`, or for no timer running at all: an empty line, or
+/// the waybar equivalent, so a bar module simply shows nothing.
+pub fn render(format: Format, template: Option<&str>, report: Option<&Report>) -> String {
+    if let Some(template) = template {
+        return report.map(|report| fill(template, report)).unwrap_or_default();
+    }
+    match format {
+        Format::Json => report
+            .and_then(|report| {
+                let response = Response { ok: true, status: Some(report.clone()), error: None };
+                serde_json::to_string(&response).ok()
+            })
+            .unwrap_or_default(),
+        Format::Waybar => serde_json::to_string(&waybar(report)).unwrap_or_default(),
+        Format::Polybar => report.map(polybar).unwrap_or_default(),
+        Format::Plain => report
+            .map(|report| fill("{phase} {remaining} {lap}/{laps} loop {loop}", report) + paused_mark(report))
+            .unwrap_or_default(),
+    }
+}
+
+fn state(report: &Report) -> &'static str {
+    match (report.running, report.paused) {
+        (false, _) => "stopped",
+        (true, true) => "paused",
+        (true, false) => "running",
+    }
+}
+
+fn paused_mark(report: &Report) -> &'static str {
+    if report.running && report.paused { " (paused)" } else { "" }
+}
+
+/// Puts the fields of `report` in place of `{phase}`, `{label}`, `{state}`,
+/// `{remaining}`, `{lap}`, `{laps}` and `{loop}`. Anything else in braces,
+/// including whatever a label brings along, is left as it is.
+pub fn fill(template: &str, report: &Report) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        rest = &rest[open..];
+        let field = rest.find('}').and_then(|close| {
+            let value = match &rest[1..close] {
+                "phase" => report.phase.name().to_owned(),
+                "label" => report.label.clone(),
+                "state" => state(report).to_owned(),
+                "remaining" => duration::clock(report.remaining_sec),
+                "lap" => report.lap.to_string(),
+                "laps" => report.laps_per_loop.to_string(),
+                "loop" => report.cycle.to_string(),
+                _ => return None,
+            };
+            Some((value, close))
+        });
+        match field {
+            Some((value, close)) => {
+                out.push_str(&value);
+                rest = &rest[close + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn waybar(report: Option<&Report>) -> Waybar {
+    let Some(report) = report else {
+        return Waybar {
+            text: String::new(),
+            tooltip: "no timer running".to_owned(),
+            class: "offline",
+            percentage: 0,
+        };
+    };
+    let planned = report.planned_sec;
+    Waybar {
+        text: fill("{remaining}", report),
+        tooltip: fill("{label} Lap: {lap}/{laps}, Loop {loop}", report) + paused_mark(report),
+        class: match (report.running, report.paused) {
+            (true, false) => report.phase.name(),
+            _ => state(report),
+        },
+        percentage: (planned.saturating_sub(report.remaining_sec) * 100).checked_div(planned).unwrap_or(0),
+    }
+}
+
+fn polybar(report: &Report) -> String {
+    let colour = match (report.running && !report.paused, report.phase) {
+        (false, _) => "#888888",
+        (true, RunState::LAP) => "#e06c75",
+        (true, _) => "#98c379",
+    };
+    format!("%{{F{}}}{}%{{F-}}", colour, fill("{remaining} {lap}/{laps}", report))
+}
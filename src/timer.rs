@@ -0,0 +1,639 @@
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use serde::{Deserialize, Serialize};
+use crate::{
+    checkpoint::{self, Checkpoint},
+    clock::{Clock, SystemClock},
+    duration,
+    history::{self, Entry, Outcome},
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RunState {
+    #[default]
+    #[serde(rename = "lap")]
+    LAP,
+    #[serde(rename = "rest_lap")]
+    RestLap,
+    #[serde(rename = "rest_loop")]
+    RestLoop,
+}
+
+impl RunState {
+    /// What the frontends show above the countdown.
+    pub fn label(&self) -> &'static str {
+        match self {
+            RunState::LAP => "grinding...",
+            RunState::RestLap => "lap resting...",
+            RunState::RestLoop => "loop resting...",
+        }
+    }
+
+    /// The name scripts see, as in the history file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            RunState::LAP => "lap",
+            RunState::RestLap => "rest_lap",
+            RunState::RestLoop => "rest_loop",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Written like `25m`, `1h30m` or `45s`, see [`duration::parse`]. The
+    /// names from before these took seconds still read as minutes.
+    #[serde(with = "duration::human", alias = "lap_dur_min")]
+    pub lap: Duration,
+    #[serde(with = "duration::human", alias = "rest_lap_min")]
+    pub rest_lap: Duration,
+    #[serde(with = "duration::human", alias = "rest_loop_min")]
+    pub rest_loop: Duration,
+    /// Laps to finish before the long rest.
+    pub laps_per_loop: u8,
+    /// Start counting a rest as soon as its lap ends, rather than waiting
+    /// to be told.
+    pub auto_start_breaks: bool,
+    /// Likewise for the lap after a lap rest.
+    pub auto_start_laps: bool,
+    /// Likewise for the first lap after the loop rest.
+    pub auto_start_loop: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            lap: Duration::from_secs(25 * 60),
+            rest_lap: Duration::from_secs(5 * 60),
+            rest_loop: Duration::from_secs(30 * 60),
+            laps_per_loop: 4,
+            auto_start_breaks: false,
+            auto_start_laps: true,
+            auto_start_loop: true,
+        }
+    }
+}
+
+impl Config {
+    pub fn duration(&self, run_state: RunState) -> Duration {
+        match run_state {
+            RunState::LAP => self.lap,
+            RunState::RestLap => self.rest_lap,
+            RunState::RestLoop => self.rest_loop,
+        }
+    }
+
+    /// Whether whatever comes after `ended` starts counting by itself.
+    pub fn auto_start(&self, ended: RunState) -> bool {
+        match ended {
+            RunState::LAP => self.auto_start_breaks,
+            RunState::RestLap => self.auto_start_laps,
+            RunState::RestLoop => self.auto_start_loop,
+        }
+    }
+}
+
+/// One phase of a custom sequence, see [`Timer::set_sequence`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Step {
+    /// Shown in place of the usual label, like "focus" or "review".
+    pub label: String,
+    #[serde(with = "duration::human")]
+    pub length: Duration,
+    /// Which of the usual phases it stands in for as far as hooks,
+    /// notifications, sounds, auto-start and the history go. A lap unless
+    /// said otherwise.
+    #[serde(default)]
+    pub kind: RunState,
+}
+
+/// How long before the end of a phase to warn that it is nearly over, in
+/// seconds. Several warnings per phase are fine, e.g. `lap_sec = [300, 60]`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Warnings {
+    pub lap_sec: Vec<u32>,
+    /// For both kinds of rest.
+    pub rest_sec: Vec<u32>,
+}
+
+impl Warnings {
+    pub fn before(&self, run_state: RunState) -> &[u32] {
+        match run_state {
+            RunState::LAP => &self.lap_sec,
+            RunState::RestLap | RunState::RestLoop => &self.rest_sec,
+        }
+    }
+}
+
+/// Something the timer did, queued until the frontend drains it with
+/// [`Timer::next_event`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    Started(RunState),
+    Paused(RunState),
+    Stopped,
+    /// The phase was cut short by [`Timer::skip`]; followed by its
+    /// `PhaseEnded`.
+    Skipped(RunState),
+    /// This much is left of the phase, as asked for by [`Warnings`].
+    Warning(RunState, Duration),
+    /// The phase was given this much more time by [`Timer::extend`].
+    Extended(RunState, Duration),
+    PhaseEnded { ended: RunState, next: RunState },
+    /// A phase is over one way or another; this is what goes into the
+    /// history.
+    Finished(Entry),
+    /// The machine was asleep for this long while the timer was running; the
+    /// countdown has already been moved forward to match.
+    Suspended(Duration),
+    /// The phase ran out while the machine was asleep; followed by its
+    /// `PhaseEnded`, after which the timer waits for the user regardless of
+    /// the auto-start settings.
+    SleptThrough(RunState),
+}
+
+/// A copy of everything a frontend needs to draw the timer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Status {
+    pub config: Config,
+    pub run_state: RunState,
+    /// What to call the current phase: its step's label, or the usual one.
+    pub label: String,
+    /// Where in a custom sequence the timer is, `None` without one.
+    pub step: Option<u8>,
+    pub running: bool,
+    pub pause: bool,
+    pub remaining: Duration,
+    /// How long the current phase is meant to last, extends included.
+    pub planned: Duration,
+    pub cur_lap: u8,
+    pub cur_loop: u8,
+    /// Laps in a loop, which a custom sequence decides for itself.
+    pub laps_per_loop: u8,
+}
+
+impl Status {
+    /// See [`Timer::remaining_sec`].
+    pub fn remaining_sec(&self) -> u32 {
+        self.remaining.as_millis().div_ceil(1000) as u32
+    }
+
+    /// Lap and loop the current phase belongs to, both counted from 1.
+    pub fn position(&self) -> (u8, u8) {
+        match (self.run_state, self.step) {
+            (RunState::LAP, _) => (self.cur_lap + 1, self.cur_loop + 1),
+            // a sequence only moves on to the next loop once it starts over
+            (RunState::RestLap, _) | (RunState::RestLoop, Some(_)) => (self.cur_lap, self.cur_loop + 1),
+            (RunState::RestLoop, None) => (self.laps_per_loop, self.cur_loop),
+        }
+    }
+
+    /// The remaining time as `MM:SS`, or `H:MM:SS` from an hour up.
+    pub fn remaining_clock(&self) -> String {
+        duration::clock(self.remaining_sec())
+    }
+}
+
+/// How much further the wall clock may run ahead of the monotonic clock
+/// between two ticks before we put it down to a suspend.
+const SUSPEND_SLACK: Duration = Duration::from_secs(2);
+
+/// The pomodoro cycle: a lap, then a lap rest, `laps_per_loop` times over,
+/// with the last rest replaced by a loop rest. Frontends call [`Timer::tick`]
+/// regularly while it is running.
+///
+/// A running phase is kept as a deadline on the [`Clock`] rather than a
+/// counter, so it ends on time however often or late it is ticked.
+pub struct Timer {
+    clock: Arc<dyn Clock>,
+    last_tick: (Instant, SystemTime),
+    config: Config,
+    run_state: RunState,
+    running: bool,
+    pause: bool,
+    deadline: Instant,
+    /// Time left in the phase, only up to date while paused.
+    remaining: Duration,
+    /// How long the current phase was meant to last when it began.
+    planned: Duration,
+    /// How much of `planned` came from [`Timer::extend`].
+    extended: Duration,
+    /// When the current phase first started counting down.
+    phase_started: Option<SystemTime>,
+    cur_lap: u8,
+    cur_loop: u8,
+    warnings: Warnings,
+    /// Replaces the lap/rest cycle when not empty.
+    sequence: Vec<Step>,
+    /// Index into `sequence` of the current phase.
+    step: usize,
+    events: VecDeque<Event>,
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Self::new(Config::default())
+    }
+}
+
+impl Timer {
+    pub fn new(config: Config) -> Self {
+        Self::with_clock(config, Arc::new(SystemClock))
+    }
+
+    pub fn with_clock(config: Config, clock: Arc<dyn Clock>) -> Self {
+        let now = clock.now();
+        Self {
+            last_tick: (now, clock.wall()),
+            clock,
+            config,
+            run_state: RunState::LAP,
+            running: false,
+            pause: true,
+            deadline: now,
+            remaining: config.duration(RunState::LAP),
+            planned: config.duration(RunState::LAP),
+            extended: Duration::ZERO,
+            phase_started: None,
+            cur_lap: 0,
+            cur_loop: 0,
+            warnings: Warnings::default(),
+            sequence: Vec::new(),
+            step: 0,
+            events: VecDeque::new(),
+        }
+    }
+
+    pub fn config(&self) -> Config {
+        self.config
+    }
+
+    pub fn warnings(&self) -> &Warnings {
+        &self.warnings
+    }
+
+    pub fn set_warnings(&mut self, warnings: Warnings) {
+        self.warnings = warnings;
+    }
+
+    pub fn sequence(&self) -> &[Step] {
+        &self.sequence
+    }
+
+    /// Runs through `sequence` over and over instead of the usual laps and
+    /// rests, or goes back to those if it is empty. Like
+    /// [`Timer::set_config`], a running session picks it up at the next
+    /// phase.
+    pub fn set_sequence(&mut self, sequence: Vec<Step>) {
+        self.sequence = sequence;
+        if self.step >= self.sequence.len() {
+            self.step = 0;
+        }
+        if !self.running {
+            self.run_state = self.sequence.first().map_or(RunState::LAP, |step| step.kind);
+            self.reset_length();
+        }
+    }
+
+    /// Replaces the durations. Only takes effect on the countdown while the
+    /// timer is stopped; a running session picks it up at the next phase.
+    pub fn set_config(&mut self, config: Config) {
+        self.config = config;
+        if !self.running {
+            self.reset_length();
+        }
+    }
+
+    /// The full length of the current phase, extends aside.
+    fn length(&self) -> Duration {
+        match self.sequence.get(self.step) {
+            Some(step) => step.length,
+            None => self.config.duration(self.run_state),
+        }
+    }
+
+    fn reset_length(&mut self) {
+        self.remaining = self.length();
+        self.planned = self.remaining;
+    }
+
+    fn laps_per_loop(&self) -> u8 {
+        match self.sequence.is_empty() {
+            true => self.config.laps_per_loop,
+            false => self.sequence.iter().filter(|step| step.kind == RunState::LAP).count() as u8,
+        }
+    }
+
+    pub fn run_state(&self) -> RunState {
+        self.run_state
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.pause
+    }
+
+    pub fn remaining(&self) -> Duration {
+        if self.pause {
+            self.remaining
+        } else {
+            self.deadline.saturating_duration_since(self.clock.now())
+        }
+    }
+
+    /// Whole seconds left, rounded up so a fresh lap reads 25:00 and the last
+    /// fraction of a second still reads 00:01.
+    pub fn remaining_sec(&self) -> u32 {
+        self.status().remaining_sec()
+    }
+
+    pub fn cur_lap(&self) -> u8 {
+        self.cur_lap
+    }
+
+    pub fn cur_loop(&self) -> u8 {
+        self.cur_loop
+    }
+
+    pub fn status(&self) -> Status {
+        let step = self.sequence.get(self.step);
+        Status {
+            config: self.config,
+            run_state: self.run_state,
+            label: step.map_or(self.run_state.label(), |step| &step.label).to_owned(),
+            step: step.map(|_| self.step as u8),
+            running: self.running,
+            pause: self.pause,
+            remaining: self.remaining(),
+            planned: self.planned,
+            cur_lap: self.cur_lap,
+            cur_loop: self.cur_loop,
+            laps_per_loop: self.laps_per_loop(),
+        }
+    }
+
+    /// The running session in a form that can be saved and handed to
+    /// [`Timer::restore`] after a restart. `None` while stopped.
+    pub fn checkpoint(&self) -> Option<Checkpoint> {
+        if !self.running {
+            return None;
+        }
+        let remaining = self.remaining();
+        let (lap, cycle) = self.position();
+        let wall = self.clock.wall();
+        Some(Checkpoint {
+            run_state: self.run_state,
+            step: self.step as u8,
+            cur_lap: self.cur_lap,
+            cur_loop: self.cur_loop,
+            lap,
+            cycle,
+            deadline_ms: (!self.pause).then(|| checkpoint::unix_millis(wall + remaining)),
+            remaining_ms: remaining.as_millis() as u64,
+            planned_sec: self.planned.as_secs(),
+            extended_sec: self.extended.as_secs(),
+            phase_started: self.phase_started.map(history::unix_secs),
+            saved_at: history::unix_secs(wall),
+        })
+    }
+
+    /// Takes over the session saved in `checkpoint`, paused. If it was
+    /// counting down when saved, the time since then is gone from it. A step
+    /// past the end of the sequence starts it over.
+    pub fn restore(&mut self, checkpoint: &Checkpoint) {
+        self.reset();
+        // the sequence may have changed in the meantime, so the step this
+        // ends up on, not the checkpoint, says what kind of phase it is
+        if usize::from(checkpoint.step) < self.sequence.len() {
+            self.step = usize::from(checkpoint.step);
+        }
+        self.run_state = self.sequence.get(self.step).map_or(checkpoint.run_state, |step| step.kind);
+        self.cur_lap = checkpoint.cur_lap;
+        self.cur_loop = checkpoint.cur_loop;
+        self.running = true;
+        self.remaining = checkpoint.remaining_at(self.clock.wall());
+        self.planned = Duration::from_secs(checkpoint.planned_sec);
+        self.extended = Duration::from_secs(checkpoint.extended_sec);
+        self.phase_started = checkpoint
+            .phase_started
+            .map(|secs| UNIX_EPOCH + Duration::from_secs(secs));
+    }
+
+    /// Starts the session, or resumes it when paused.
+    pub fn start(&mut self) {
+        if !self.pause {
+            return;
+        }
+        self.pause = false;
+        self.running = true;
+        self.last_tick = (self.clock.now(), self.clock.wall());
+        self.deadline = self.last_tick.0 + self.remaining;
+        self.phase_started.get_or_insert(self.last_tick.1);
+        self.events.push_back(Event::Started(self.run_state));
+    }
+
+    pub fn pause(&mut self) {
+        if self.pause {
+            return;
+        }
+        self.tick();
+        if self.pause {
+            // the phase ran out just now and paused on its own
+            return;
+        }
+        self.pause = true;
+        self.remaining = self.deadline.saturating_duration_since(self.last_tick.0);
+        self.events.push_back(Event::Paused(self.run_state));
+    }
+
+    /// Abandons the session and goes back to the first lap.
+    pub fn stop(&mut self) {
+        self.tick();
+        let left = self.remaining();
+        self.finish(Outcome::Aborted, self.clock.wall(), left);
+        self.reset();
+        self.events.push_back(Event::Stopped);
+    }
+
+    /// Back to the start of the first phase, keeping the config, warnings,
+    /// sequence and any events not yet drained.
+    fn reset(&mut self) {
+        let events = std::mem::take(&mut self.events);
+        let warnings = std::mem::take(&mut self.warnings);
+        let sequence = std::mem::take(&mut self.sequence);
+        *self = Self::with_clock(self.config, Arc::clone(&self.clock));
+        self.events = events;
+        self.warnings = warnings;
+        self.set_sequence(sequence);
+    }
+
+    /// Ends the current phase right away and moves on to the next one, as if
+    /// its deadline had just passed.
+    pub fn skip(&mut self) {
+        if !self.running {
+            return;
+        }
+        self.tick();
+        let left = self.remaining();
+        self.deadline = self.clock.now();
+        self.events.push_back(Event::Skipped(self.run_state));
+        // a rest skipped before it was ever started still counts as skipped
+        self.phase_started.get_or_insert(self.clock.wall());
+        self.advance(Outcome::Skipped, left);
+    }
+
+    /// Adds `by` to the current phase, whether it is counting down or paused.
+    /// An amount too large to add up is ignored.
+    pub fn extend(&mut self, by: Duration) {
+        if !self.running {
+            return;
+        }
+        self.tick();
+        let (Some(planned), Some(extended)) = (self.planned.checked_add(by), self.extended.checked_add(by)) else {
+            return;
+        };
+        if self.pause {
+            let Some(remaining) = self.remaining.checked_add(by) else { return };
+            self.remaining = remaining;
+        } else {
+            let Some(deadline) = self.deadline.checked_add(by) else { return };
+            self.deadline = deadline;
+        }
+        self.planned = planned;
+        self.extended = extended;
+        self.events.push_back(Event::Extended(self.run_state, by));
+    }
+
+    /// Catches up with the clock, moving on to the next phase whenever the
+    /// current one has passed its deadline. Does nothing unless the timer is
+    /// running.
+    pub fn tick(&mut self) {
+        if !self.running || self.pause {
+            return;
+        }
+        let now = self.clock.now();
+        let wall = self.clock.wall();
+        let previous = self.last_tick.0;
+        let mono_elapsed = now.saturating_duration_since(self.last_tick.0);
+        let wall_elapsed = wall.duration_since(self.last_tick.1).unwrap_or_default();
+        self.last_tick = (now, wall);
+        // The monotonic clock stands still while the machine is suspended;
+        // whatever the wall clock saw on top of it has to come off the phase.
+        let slept = wall_elapsed.saturating_sub(mono_elapsed);
+        let suspended = slept > SUSPEND_SLACK;
+        if suspended {
+            self.deadline = self.deadline.checked_sub(slept).unwrap_or(now);
+            self.events.push_back(Event::Suspended(slept));
+        }
+        // warn about whatever offsets the countdown went past since the last
+        // tick, unless it ran out altogether
+        let left_before = self.deadline.saturating_duration_since(previous);
+        let left = self.deadline.saturating_duration_since(now);
+        if !left.is_zero() {
+            for &sec in self.warnings.before(self.run_state) {
+                let at = Duration::from_secs(sec as u64);
+                if left <= at && at < left_before {
+                    self.events.push_back(Event::Warning(self.run_state, at));
+                }
+            }
+        }
+        if suspended && self.deadline <= now {
+            // Nobody was there for whatever came after this phase, so end it
+            // where it ran out and wait rather than completing the rest.
+            self.events.push_back(Event::SleptThrough(self.run_state));
+            self.advance(Outcome::Completed, Duration::ZERO);
+            self.pause = true;
+            self.phase_started = None;
+            return;
+        }
+        while !self.pause && self.deadline <= now {
+            self.advance(Outcome::Completed, Duration::ZERO);
+        }
+    }
+
+    pub fn next_event(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+
+    /// Where `instant` falls on the wall clock, going by the last tick.
+    fn wall_at(&self, instant: Instant) -> SystemTime {
+        let (now, wall) = self.last_tick;
+        if instant <= now {
+            wall - (now - instant)
+        } else {
+            wall + (instant - now)
+        }
+    }
+
+    fn position(&self) -> (u8, u8) {
+        self.status().position()
+    }
+
+    fn finish(&mut self, outcome: Outcome, ended_at: SystemTime, left: Duration) {
+        let Some(started_at) = self.phase_started.take() else {
+            // never got going, nothing worth logging
+            return;
+        };
+        let (lap, cycle) = self.position();
+        self.events.push_back(Event::Finished(Entry {
+            phase: self.run_state,
+            lap,
+            cycle,
+            started_at: history::unix_secs(started_at),
+            ended_at: history::unix_secs(ended_at),
+            planned_sec: self.planned.as_secs(),
+            extended_sec: self.extended.as_secs(),
+            actual_sec: self.planned.saturating_sub(left).as_secs(),
+            outcome,
+        }));
+    }
+
+    fn advance(&mut self, outcome: Outcome, left: Duration) {
+        let ended_at = self.wall_at(self.deadline);
+        self.finish(outcome, ended_at, left);
+        let ended = self.run_state;
+        if !self.config.auto_start(ended) {
+            // wait for the user to confirm they've put the tomato down, or
+            // picked it back up
+            self.pause = true;
+        }
+        if !self.sequence.is_empty() {
+            if ended == RunState::LAP {
+                self.cur_lap += 1;
+            }
+            self.step = (self.step + 1) % self.sequence.len();
+            if self.step == 0 {
+                self.cur_lap = 0;
+                self.cur_loop += 1;
+            }
+            self.run_state = self.sequence[self.step].kind;
+        } else if ended == RunState::LAP {
+            self.cur_lap += 1;
+            if self.cur_lap >= self.config.laps_per_loop {
+                self.run_state = RunState::RestLoop;
+                self.cur_lap = 0;
+                self.cur_loop += 1;
+            } else {
+                self.run_state = RunState::RestLap;
+            }
+        } else {
+            self.run_state = RunState::LAP;
+        }
+        let duration = self.length();
+        // chain off the old deadline, not the moment we noticed it passed
+        self.deadline += duration;
+        self.remaining = duration;
+        self.planned = duration;
+        self.extended = Duration::ZERO;
+        self.phase_started = if self.pause { None } else { Some(ended_at) };
+        self.events.push_back(Event::PhaseEnded { ended, next: self.run_state });
+    }
+}
@@ -0,0 +1,352 @@
+use std::{io, process::ExitCode, time::Duration};
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event as TermEvent, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Alignment, Constraint, Flex, Layout, Rect},
+    style::Stylize,
+    text::Line,
+    widgets::{Block, Paragraph},
+};
+use pomodoro_rs::{
+    checkpoint::Checkpoint,
+    duration,
+    paths,
+    services::Services,
+    settings::{Error, MAX_DURATION, Settings},
+    timer::{Config, Status},
+    worker::{Command, Worker},
+};
+
+/// Big digits for the countdown, five rows tall.
+const DIGITS: [[&str; 5]; 10] = [
+    ["███", "█ █", "█ █", "█ █", "███"],
+    ["  █", "  █", "  █", "  █", "  █"],
+    ["███", "  █", "███", "█  ", "███"],
+    ["███", "  █", "███", "  █", "███"],
+    ["█ █", "█ █", "███", "  █", "  █"],
+    ["███", "█  ", "███", "  █", "███"],
+    ["███", "█  ", "███", "█ █", "███"],
+    ["███", "  █", "  █", "  █", "  █"],
+    ["███", "█ █", "███", "█ █", "███"],
+    ["███", "█ █", "███", "  █", "███"],
+];
+const COLON: [&str; 5] = [" ", "█", " ", "█", " "];
+
+enum Screen {
+    Timer,
+    Setting,
+    /// Offering to pick up the session the last run left unfinished.
+    Recover,
+}
+
+struct App {
+    services: Services,
+    settings: Settings,
+    screen: Screen,
+    recovered: Option<Checkpoint>,
+    /// Settings being edited and which line is selected.
+    draft: Config,
+    volume: u8,
+    selected: usize,
+    message: Option<String>,
+    /// `config.toml` failed to load, so it gets backed up before the first
+    /// save rather than overwritten with the defaults.
+    config_broken: bool,
+    quit: bool,
+}
+
+pub fn run() -> ExitCode {
+    let (settings, err) = Settings::load_or_default();
+    let mut services = Services::spawn(&settings, Worker::spawn(settings.new_timer(), || {}));
+    // complaining about a busy socket would only get drawn over
+    if let Some(socket) = paths::socket_path() {
+        let _ = services.serve(&socket);
+    }
+    #[cfg(feature = "dbus")]
+    let _ = services.serve_dbus();
+    // the old checkpoint is left alone until the user has decided about it
+    let recovered = services.take_checkpoint();
+    let mut app = App {
+        services,
+        draft: settings.timer,
+        volume: settings.sounds.volume,
+        settings,
+        screen: if recovered.is_some() { Screen::Recover } else { Screen::Timer },
+        recovered,
+        selected: 0,
+        config_broken: err.is_some(),
+        message: err.map(|err| err.to_string()),
+        quit: false,
+    };
+
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+
+    // a session still going is left in the checkpoint for the next start
+    drop(app);
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("pomodoro: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+impl App {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        while !self.quit {
+            terminal.draw(|frame| self.draw(frame))?;
+            if event::poll(Duration::from_millis(200))?
+                && let TermEvent::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+            {
+                match self.screen {
+                    // raw mode turns Ctrl-C into a key like any other
+                    _ if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.quit = true
+                    }
+                    Screen::Timer => self.timer_key(key.code),
+                    Screen::Setting => self.setting_key(key.code),
+                    Screen::Recover => self.recover_key(key.code),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn timer_key(&mut self, code: KeyCode) {
+        let status = self.services.worker().status();
+        match code {
+            KeyCode::Char(' ') | KeyCode::Char('p') => self.services.worker().send(match (status.running, status.pause) {
+                (false, _) => Command::Start,
+                (true, true) => Command::Resume,
+                (true, false) => Command::Pause,
+            }),
+            KeyCode::Char('s') if status.running && status.pause => self.services.worker().send(Command::Stop),
+            KeyCode::Char('c') if !status.running => {
+                self.draft = self.settings.timer;
+                self.volume = self.settings.sounds.volume;
+                self.selected = 0;
+                self.screen = Screen::Setting;
+            }
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            _ => {}
+        }
+    }
+
+    fn recover_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char('r') | KeyCode::Enter => {
+                self.services.restore();
+                self.services.worker().send(Command::Resume);
+            }
+            KeyCode::Char('d') => self.services.discard(),
+            KeyCode::Char('q') | KeyCode::Esc => {
+                self.quit = true;
+                return;
+            }
+            _ => return,
+        }
+        self.recovered = None;
+        self.screen = Screen::Timer;
+    }
+
+    fn setting_key(&mut self, code: KeyCode) {
+        let step: i32 = match code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected = self.selected.saturating_sub(1);
+                return;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.setting_rows() - 1);
+                return;
+            }
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::Char('-') => -1,
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Char('+') => 1,
+            KeyCode::Enter => {
+                self.confirm();
+                return;
+            }
+            KeyCode::Esc => {
+                self.screen = Screen::Timer;
+                return;
+            }
+            _ => return,
+        };
+        let draft = &mut self.draft;
+        match self.selected {
+            0 => draft.lap = nudge(draft.lap, step),
+            1 => draft.rest_lap = nudge(draft.rest_lap, step),
+            2 => draft.rest_loop = nudge(draft.rest_loop, step),
+            3 => draft.laps_per_loop = draft.laps_per_loop.saturating_add_signed(step as i8).clamp(1, 12),
+            4 => draft.auto_start_breaks = step > 0,
+            5 => draft.auto_start_laps = step > 0,
+            6 => draft.auto_start_loop = step > 0,
+            _ => self.volume = self.volume.saturating_add_signed(step as i8 * 5).min(100),
+        }
+    }
+
+    fn confirm(&mut self) {
+        let mut settings = Settings {
+            timer: self.draft,
+            ..self.settings.clone()
+        };
+        settings.sounds.volume = self.volume;
+        let applied = settings.apply(self.config_broken, &self.services.worker().handle(), self.services.player());
+        if let Err(err @ Error::Invalid(_)) = applied {
+            self.message = Some(err.to_string());
+            return;
+        }
+        self.config_broken &= applied.is_err();
+        self.message = match applied {
+            Ok(Some(backup)) => Some(format!("the old config is kept in {}", backup.display())),
+            Ok(None) => None,
+            Err(err) => Some(err.to_string()),
+        };
+        self.settings = settings;
+        self.screen = Screen::Timer;
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [body, message, help] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let help_text = match self.screen {
+            Screen::Timer => self.draw_timer(frame, body),
+            Screen::Setting => self.draw_setting(frame, body),
+            Screen::Recover => self.draw_recover(frame, body),
+        };
+        if let Some(text) = &self.message {
+            frame.render_widget(Paragraph::new(text.as_str()).red().alignment(Alignment::Center), message);
+        }
+        frame.render_widget(Paragraph::new(help_text).dim().alignment(Alignment::Center), help);
+    }
+
+    fn draw_timer(&self, frame: &mut Frame, area: Rect) -> &'static str {
+        let status = self.services.worker().status();
+        draw_status(frame, area, &status);
+        match (status.running, status.pause) {
+            (false, _) => "space start · c settings · q quit",
+            (true, true) => "space resume · s stop · q quit",
+            (true, false) => "space pause · q quit",
+        }
+    }
+
+    fn draw_recover(&self, frame: &mut Frame, area: Rect) -> &'static str {
+        if let Some(checkpoint) = &self.recovered {
+            let [title, body] = Layout::vertical([Constraint::Length(2), Constraint::Fill(1)]).areas(area);
+            frame.render_widget(Paragraph::new("unfinished session").bold().alignment(Alignment::Center), title);
+            draw_status(frame, body, &self.settings.preview(checkpoint));
+        }
+        "r resume · d discard · q quit"
+    }
+
+    /// The volume is only there when something can be heard.
+    fn setting_rows(&self) -> usize {
+        if self.services.sound_error().is_some() { 7 } else { 8 }
+    }
+
+    fn draw_setting(&self, frame: &mut Frame, area: Rect) -> &'static str {
+        let fields = [
+            ("Lap duration", duration::format(self.draft.lap), ""),
+            ("Lap rest duration", duration::format(self.draft.rest_lap), ""),
+            ("Loop rest duration", duration::format(self.draft.rest_loop), ""),
+            ("Laps before loop rest", self.draft.laps_per_loop.to_string(), "laps"),
+            ("Start rests", yes_no(self.draft.auto_start_breaks), ""),
+            ("Start laps after rest", yes_no(self.draft.auto_start_laps), ""),
+            ("Start next loop", yes_no(self.draft.auto_start_loop), ""),
+            ("Volume", self.volume.to_string(), "%"),
+        ];
+        let mut lines: Vec<Line> = fields
+            .into_iter()
+            .take(self.setting_rows())
+            .enumerate()
+            .map(|(i, (name, value, unit))| {
+                let line = Line::from(format!("{:<22} {:>7} {:<3}", name, value, unit));
+                if i == self.selected { line.reversed() } else { line }
+            })
+            .collect();
+        let mut notes = Vec::new();
+        if !self.settings.sequence.is_empty() {
+            notes.push("(the sequence in config.toml is used instead of these)".to_owned());
+        }
+        if let Some(err) = self.services.sound_error() {
+            notes.push(format!("(no sound: {})", err));
+        }
+        for note in notes {
+            lines.extend(wrap(&note, 38).into_iter().map(|line| Line::from(line).dim()));
+        }
+        let height = lines.len() as u16 + 2;
+        let [area] = Layout::vertical([Constraint::Length(height)]).flex(Flex::Center).areas(area);
+        let [area] = Layout::horizontal([Constraint::Length(40)]).flex(Flex::Center).areas(area);
+        frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(" setting ")), area);
+        "↑↓ select · ←→ change · enter confirm · esc back"
+    }
+}
+
+/// The phase's label, the big countdown and where in the loop it is.
+fn draw_status(frame: &mut Frame, area: Rect, status: &Status) {
+    let [label, clock, laps] = Layout::vertical([
+        Constraint::Length(2),
+        Constraint::Length(6),
+        Constraint::Length(1),
+    ])
+    .flex(Flex::Center)
+    .areas(area);
+    frame.render_widget(Paragraph::new(status.label.as_str()).alignment(Alignment::Center), label);
+    frame.render_widget(Paragraph::new(big_text(&status.remaining_clock())).bold().alignment(Alignment::Center), clock);
+    let counter = format!("Lap: {}/{}, Loop {}", status.cur_lap, status.laps_per_loop, status.cur_loop);
+    frame.render_widget(Paragraph::new(counter).alignment(Alignment::Center), laps);
+}
+
+/// `text` in [`DIGITS`], with every block doubled so it doesn't look squashed
+/// in a terminal cell.
+fn big_text(text: &str) -> Vec<Line<'static>> {
+    (0..5)
+        .map(|row| {
+            let glyphs: Vec<&str> = text
+                .chars()
+                .map(|c| match c.to_digit(10) {
+                    Some(digit) => DIGITS[digit as usize][row],
+                    None => COLON[row],
+                })
+                .collect();
+            Line::from(glyphs.join(" ").chars().flat_map(|c| [c, c]).collect::<String>())
+        })
+        .collect()
+}
+
+/// One press of ←/→ on a duration: a minute, or five seconds under a minute.
+fn nudge(length: Duration, step: i32) -> Duration {
+    let secs = length.as_secs() as i64;
+    let by = if secs < 60 || (secs == 60 && step < 0) { 5 } else { 60 };
+    let secs = (secs + by * step as i64).clamp(5, MAX_DURATION.as_secs() as i64);
+    Duration::from_secs(secs as u64)
+}
+
+/// `text` broken into lines of at most `width` characters, between words
+/// where it can be.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    for word in text.split_whitespace() {
+        let line = lines.last_mut().unwrap();
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(String::new());
+        }
+        let line = lines.last_mut().unwrap();
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    lines
+}
+
+fn yes_no(on: bool) -> String {
+    if on { "yes" } else { "no" }.to_owned()
+}
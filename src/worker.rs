@@ -0,0 +1,230 @@
+use std::{
+    sync::{Arc, Mutex, mpsc::{self, Receiver, RecvTimeoutError, Sender}},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+use crate::{
+    checkpoint::Checkpoint,
+    timer::{Config, Event, Status, Timer},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    Start,
+    Pause,
+    Resume,
+    Stop,
+    Skip,
+    Extend(Duration),
+    SetConfig(Config),
+    /// Picks up an interrupted session, paused; see [`Timer::restore`].
+    Restore(Checkpoint),
+}
+
+enum Msg {
+    Command(Command),
+    /// A command whose sender waits for the status it results in.
+    Call(Command, Sender<Status>),
+    Shutdown,
+}
+
+/// What the worker last published about its timer.
+struct Published {
+    status: Status,
+    checkpoint: Option<Checkpoint>,
+}
+
+/// Somewhere the worker's events go.
+enum Subscriber {
+    Events(Sender<Event>),
+    WithStatus(Sender<(Event, Status)>),
+}
+
+impl Subscriber {
+    /// False once the receiving end is gone.
+    fn send(&self, event: Event, status: &Status) -> bool {
+        match self {
+            Subscriber::Events(tx) => tx.send(event).is_ok(),
+            Subscriber::WithStatus(tx) => tx.send((event, status.clone())).is_ok(),
+        }
+    }
+}
+
+/// A cheap, cloneable way to drive a [`Worker`] from another thread.
+#[derive(Clone)]
+pub struct Handle {
+    commands: Sender<Msg>,
+    published: Arc<Mutex<Published>>,
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+}
+
+impl Handle {
+    pub fn send(&self, command: Command) {
+        // only fails once the worker is gone, at which point nobody cares
+        let _ = self.commands.send(Msg::Command(command));
+    }
+
+    /// Like [`Handle::send`], but waits for the worker to carry the command
+    /// out and returns the status it left behind. `None` if the worker is
+    /// gone.
+    pub fn call(&self, command: Command) -> Option<Status> {
+        let (tx, rx) = mpsc::channel();
+        self.commands.send(Msg::Call(command, tx)).ok()?;
+        rx.recv().ok()
+    }
+
+    pub fn status(&self) -> Status {
+        self.published.lock().unwrap().status.clone()
+    }
+
+    /// The session as it would have to be saved right now to survive a
+    /// restart, `None` when there is no session.
+    pub fn checkpoint(&self) -> Option<Checkpoint> {
+        self.published.lock().unwrap().checkpoint
+    }
+
+    /// Every event the timer produces from now on. The stream ends when the
+    /// worker shuts down.
+    pub fn subscribe(&self) -> Receiver<Event> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers.lock().unwrap().push(Subscriber::Events(tx));
+        rx
+    }
+
+    /// Like [`Handle::subscribe`], with the status the worker published
+    /// along with each event, for whoever gets to an event too late for
+    /// [`Handle::status`] to still describe it.
+    pub fn subscribe_with_status(&self) -> Receiver<(Event, Status)> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers.lock().unwrap().push(Subscriber::WithStatus(tx));
+        rx
+    }
+}
+
+/// Owns the [`Timer`] on a thread of its own for as long as the app lives,
+/// ticking it and applying [`Command`]s. Dropping the worker stops the
+/// thread and waits for it; a session still in progress is left as it is,
+/// for a checkpoint to pick up.
+pub struct Worker {
+    handle: Handle,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Worker {
+    /// `on_change` is called from the worker thread after every change to
+    /// the status, e.g. to wake up the UI.
+    pub fn spawn(timer: Timer, on_change: impl Fn() + Send + 'static) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let handle = Handle {
+            commands: tx,
+            published: Arc::new(Mutex::new(Published {
+                status: timer.status(),
+                checkpoint: timer.checkpoint(),
+            })),
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+        };
+        let published = Arc::clone(&handle.published);
+        let subscribers = Arc::clone(&handle.subscribers);
+        let thread = thread::spawn(move || run(timer, rx, published, subscribers, on_change));
+        Self {
+            handle,
+            thread: Some(thread),
+        }
+    }
+
+    pub fn handle(&self) -> Handle {
+        self.handle.clone()
+    }
+
+    pub fn send(&self, command: Command) {
+        self.handle.send(command);
+    }
+
+    pub fn status(&self) -> Status {
+        self.handle.status()
+    }
+
+    pub fn subscribe(&self) -> Receiver<Event> {
+        self.handle.subscribe()
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        let _ = self.handle.commands.send(Msg::Shutdown);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn run(
+    mut timer: Timer,
+    rx: Receiver<Msg>,
+    published: Arc<Mutex<Published>>,
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+    on_change: impl Fn(),
+) {
+    loop {
+        let msg = if timer.is_running() && !timer.is_paused() {
+            // wake up whenever the displayed second is about to change
+            let wait = match timer.remaining().subsec_nanos() {
+                0 => Duration::from_secs(1),
+                nanos => Duration::from_nanos(nanos as u64),
+            };
+            match rx.recv_timeout(wait) {
+                Ok(msg) => Some(msg),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        } else {
+            match rx.recv() {
+                Ok(msg) => Some(msg),
+                Err(_) => break,
+            }
+        };
+        let mut reply = None;
+        match msg {
+            Some(Msg::Shutdown) => break,
+            Some(Msg::Command(command)) => apply(&mut timer, command),
+            Some(Msg::Call(command, tx)) => {
+                apply(&mut timer, command);
+                reply = Some(tx);
+            }
+            None => {}
+        }
+        timer.tick();
+
+        // publish the status first so subscribers reacting to an event see it
+        let status = timer.status();
+        *published.lock().unwrap() = Published {
+            status: status.clone(),
+            checkpoint: timer.checkpoint(),
+        };
+        let mut subscribers = subscribers.lock().unwrap();
+        while let Some(event) = timer.next_event() {
+            subscribers.retain(|subscriber| subscriber.send(event, &status));
+        }
+        drop(subscribers);
+        if let Some(tx) = reply {
+            let _ = tx.send(status);
+        }
+        on_change();
+    }
+    // handles outliving the worker would otherwise keep every stream open
+    subscribers.lock().unwrap().clear();
+}
+
+fn apply(timer: &mut Timer, command: Command) {
+    match command {
+        Command::Start if !timer.is_running() => timer.start(),
+        Command::Resume if timer.is_running() => timer.start(),
+        Command::Start | Command::Resume => {}
+        Command::Pause => timer.pause(),
+        Command::Stop => timer.stop(),
+        Command::Skip => timer.skip(),
+        Command::Extend(by) => timer.extend(by),
+        Command::SetConfig(config) => timer.set_config(config),
+        Command::Restore(checkpoint) => timer.restore(&checkpoint),
+    }
+}
@@ -0,0 +1,28 @@
+//! Fixtures shared by the integration tests; each test crate only uses some.
+#![allow(dead_code)]
+
+use std::{sync::Arc, thread, time::{Duration, Instant}};
+use pomodoro_rs::{
+    clock::FakeClock,
+    timer::{Config, Timer},
+    worker::Worker,
+};
+
+/// A worker with the default config whose clock never moves.
+pub fn fake_worker() -> Worker {
+    worker_on(Arc::new(FakeClock::new()))
+}
+
+/// A worker with the default config, going by `clock`.
+pub fn worker_on(clock: Arc<FakeClock>) -> Worker {
+    Worker::spawn(Timer::with_clock(Config::default(), clock), || {})
+}
+
+/// Waits a little for something another thread is doing.
+pub fn eventually(what: &str, check: impl Fn() -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while !check() {
+        assert!(Instant::now() < deadline, "timed out waiting for {what}");
+        thread::sleep(Duration::from_millis(10));
+    }
+}
@@ -0,0 +1,100 @@
+#![cfg(feature = "dbus")]
+
+mod common;
+
+use std::{
+    io::{BufRead, BufReader},
+    process::{Child, Command, Stdio},
+};
+use pomodoro_rs::dbus::{INTERFACE, NAME, PATH, Service};
+use zbus::{
+    blocking::{Connection, Proxy, connection, proxy},
+    proxy::CacheProperties,
+};
+use common::fake_worker;
+
+/// A `dbus-daemon` of our own, so the tests neither need nor disturb the
+/// desktop's session bus.
+struct Bus {
+    daemon: Child,
+    address: String,
+}
+
+impl Bus {
+    fn start() -> Option<Self> {
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|err| eprintln!("skipping, no dbus-daemon: {}", err))
+            .ok()?;
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take()?).read_line(&mut address).ok()?;
+        Some(Self {
+            daemon,
+            address: address.trim().to_owned(),
+        })
+    }
+
+    fn connect(&self) -> Connection {
+        connection::Builder::address(self.address.as_str()).unwrap().build().unwrap()
+    }
+}
+
+impl Drop for Bus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
+
+fn pomodoro(connection: &Connection) -> Proxy<'_> {
+    proxy::Builder::new(connection)
+        .destination(NAME).unwrap()
+        .path(PATH).unwrap()
+        .interface(INTERFACE).unwrap()
+        .cache_properties(CacheProperties::No)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn methods_drive_the_timer_and_properties_follow() {
+    let Some(bus) = Bus::start() else { return };
+    let worker = fake_worker();
+    let _service = Service::spawn_at(&bus.address, worker.handle()).unwrap();
+    let connection = bus.connect();
+    let proxy = pomodoro(&connection);
+
+    assert_eq!(proxy.get_property::<String>("State").unwrap(), "stopped");
+    proxy.call_method("Start", &()).unwrap();
+    assert_eq!(proxy.get_property::<String>("State").unwrap(), "running");
+    assert_eq!(proxy.get_property::<String>("Phase").unwrap(), "lap");
+    assert_eq!(proxy.get_property::<u32>("RemainingSeconds").unwrap(), 25 * 60);
+
+    proxy.call_method("Skip", &()).unwrap();
+    assert_eq!(proxy.get_property::<String>("Phase").unwrap(), "rest_lap");
+    assert_eq!(proxy.get_property::<u32>("Lap").unwrap(), 1);
+    assert_eq!(proxy.get_property::<u32>("Loop").unwrap(), 0);
+
+    // a lap ending waits for the rest to be started, so there is nothing to pause
+    assert!(proxy.call_method("Pause", &()).is_err());
+    proxy.call_method("Stop", &()).unwrap();
+    assert_eq!(proxy.get_property::<String>("State").unwrap(), "stopped");
+}
+
+#[test]
+fn phase_changes_are_signalled() {
+    let Some(bus) = Bus::start() else { return };
+    let worker = fake_worker();
+    let _service = Service::spawn_at(&bus.address, worker.handle()).unwrap();
+    let connection = bus.connect();
+    let proxy = pomodoro(&connection);
+    let mut signals = proxy.receive_signal("PhaseChanged").unwrap();
+
+    proxy.call_method("Start", &()).unwrap();
+    proxy.call_method("Skip", &()).unwrap();
+    let message = signals.next().unwrap();
+    let (ended, next): (String, String) = message.body().deserialize().unwrap();
+    assert_eq!((ended.as_str(), next.as_str()), ("lap", "rest_lap"));
+}
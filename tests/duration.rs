@@ -0,0 +1,32 @@
+use std::time::Duration;
+use pomodoro_rs::duration::{clock, format, parse};
+
+#[test]
+fn parses_human_durations() {
+    assert_eq!(parse("5"), Ok(Duration::from_secs(5 * 60)));
+    assert_eq!(parse("5m"), Ok(Duration::from_secs(5 * 60)));
+    assert_eq!(parse("45s"), Ok(Duration::from_secs(45)));
+    assert_eq!(parse("1h30m"), Ok(Duration::from_secs(90 * 60)));
+    assert_eq!(parse(" 1h 5m 30s "), Ok(Duration::from_secs(3930)));
+}
+
+#[test]
+fn rejects_nonsense() {
+    for text in ["", "m", "5x", "1h30", "-5m", "307445734561825861"] {
+        assert!(parse(text).is_err(), "{text:?}");
+    }
+}
+
+#[test]
+fn formats_the_way_it_parses() {
+    assert_eq!(format(Duration::from_secs(25 * 60)), "25m");
+    assert_eq!(format(Duration::from_secs(90 * 60)), "1h30m");
+    assert_eq!(format(Duration::from_secs(3930)), "1h5m30s");
+    assert_eq!(format(Duration::from_secs(45)), "45s");
+    assert_eq!(format(Duration::ZERO), "0s");
+    for secs in [1, 59, 60, 61, 3600, 86399] {
+        assert_eq!(parse(&format(Duration::from_secs(secs))), Ok(Duration::from_secs(secs)));
+    }
+    assert_eq!(clock(25 * 60), "25:00");
+    assert_eq!(clock(5400 + 7), "1:30:07");
+}
@@ -0,0 +1,165 @@
+mod common;
+
+use std::{fs, sync::Arc, time::Duration};
+use pomodoro_rs::{
+    clock::FakeClock,
+    hooks::{Fired, Hook, HookRunner, Hooks, Transitions},
+    timer::{Config, RunState, Timer},
+    worker::Command,
+};
+
+fn minutes(min: u64) -> Duration {
+    Duration::from_secs(min * 60)
+}
+
+/// A timer whose events go through [`Transitions`] as each change makes
+/// them, with the status that change left, the way the worker hands them out.
+struct Fed {
+    timer: Timer,
+    transitions: Transitions,
+    fired: Vec<Fired>,
+}
+
+impl Fed {
+    fn new(timer: Timer) -> Self {
+        Self { timer, transitions: Transitions::default(), fired: Vec::new() }
+    }
+
+    fn step(&mut self, change: impl FnOnce(&mut Timer)) {
+        change(&mut self.timer);
+        let status = self.timer.status();
+        while let Some(event) = self.timer.next_event() {
+            self.fired.extend(self.transitions.feed(event, &status));
+        }
+    }
+
+    /// Everything fired since the last call.
+    fn fired(&mut self) -> Vec<Fired> {
+        std::mem::take(&mut self.fired)
+    }
+}
+
+fn hooks(fired: &[Fired]) -> Vec<Hook> {
+    fired.iter().map(|fired| fired.hook).collect()
+}
+
+#[test]
+fn events_map_to_hooks() {
+    let clock = Arc::new(FakeClock::new());
+    let config = Config { laps_per_loop: 1, ..Config::default() };
+    let mut timer = Fed::new(Timer::with_clock(config, clock.clone()));
+
+    timer.step(Timer::start);
+    timer.step(Timer::pause);
+    timer.step(Timer::start);
+    assert_eq!(hooks(&timer.fired()), [Hook::LapStart, Hook::Pause, Hook::Resume]);
+
+    clock.advance(minutes(25));
+    timer.step(Timer::tick);
+    let lap_end = timer.fired();
+    assert_eq!(hooks(&lap_end), [Hook::LapEnd]);
+    assert_eq!((lap_end[0].phase, lap_end[0].lap, lap_end[0].cycle), (RunState::LAP, 1, 1));
+    assert_eq!(lap_end[0].duration_sec, 25 * 60);
+
+    // the loop rest runs out and the next lap carries on by itself
+    timer.step(Timer::start);
+    clock.advance(minutes(30));
+    timer.step(Timer::tick);
+    let rest = timer.fired();
+    assert_eq!(hooks(&rest), [Hook::RestStart, Hook::RestEnd, Hook::LoopComplete, Hook::LapStart]);
+    assert_eq!(rest[0].phase, RunState::RestLoop);
+    assert_eq!(rest[3].phase, RunState::LAP);
+
+    timer.step(Timer::stop);
+    assert_eq!(hooks(&timer.fired()), [Hook::Stop]);
+}
+
+#[test]
+fn a_rest_skipped_while_waiting_does_not_start_the_lap() {
+    let clock = Arc::new(FakeClock::new());
+    let mut timer = Fed::new(Timer::with_clock(Config::default(), clock.clone()));
+
+    timer.step(Timer::start);
+    timer.step(Timer::skip);
+    timer.step(Timer::skip);
+    assert_eq!(hooks(&timer.fired()), [Hook::LapStart, Hook::LapEnd, Hook::RestEnd]);
+    timer.step(Timer::start);
+    assert_eq!(hooks(&timer.fired()), [Hook::LapStart]);
+}
+
+#[test]
+fn phases_started_by_themselves_fire_their_start() {
+    let clock = Arc::new(FakeClock::new());
+    let config = Config { auto_start_breaks: true, auto_start_laps: false, ..Config::default() };
+    let mut timer = Fed::new(Timer::with_clock(config, clock.clone()));
+
+    timer.step(Timer::start);
+    clock.advance(minutes(25));
+    timer.step(Timer::tick);
+    assert_eq!(hooks(&timer.fired()), [Hook::LapStart, Hook::LapEnd, Hook::RestStart]);
+    clock.advance(minutes(5));
+    timer.step(Timer::tick);
+    assert_eq!(hooks(&timer.fired()), [Hook::RestEnd]);
+    timer.step(Timer::start);
+    assert_eq!(hooks(&timer.fired()), [Hook::LapStart]);
+}
+
+#[test]
+fn phases_are_described_as_they_were_when_they_fired() {
+    let clock = Arc::new(FakeClock::new());
+    let config = Config { auto_start_breaks: true, ..Config::default() };
+    let mut timer = Fed::new(Timer::with_clock(config, clock.clone()));
+
+    timer.step(Timer::start);
+    timer.step(Timer::skip);
+    timer.step(Timer::skip);
+    let fired = timer.fired();
+    assert_eq!(hooks(&fired), [Hook::LapStart, Hook::LapEnd, Hook::RestStart, Hook::RestEnd, Hook::LapStart]);
+    // not the lap that was up by the time the rest was over
+    assert_eq!((fired[2].phase, fired[2].lap, fired[2].cycle), (RunState::RestLap, 1, 1));
+    assert_eq!(fired[2].duration_sec, 5 * 60);
+    assert_eq!((fired[4].lap, fired[4].duration_sec), (2, 25 * 60));
+}
+
+#[test]
+fn a_phase_slept_through_does_not_start_the_next() {
+    let clock = Arc::new(FakeClock::new());
+    let config = Config { auto_start_breaks: true, ..Config::default() };
+    let mut timer = Fed::new(Timer::with_clock(config, clock.clone()));
+
+    timer.step(Timer::start);
+    clock.suspend(minutes(60));
+    timer.step(Timer::tick);
+    assert_eq!(hooks(&timer.fired()), [Hook::LapStart, Hook::LapEnd]);
+    timer.step(Timer::start);
+    assert_eq!(hooks(&timer.fired()), [Hook::RestStart]);
+}
+
+#[test]
+fn hooks_run_with_the_phase_in_their_environment() {
+    let dir = std::env::temp_dir().join(format!("pomodoro-hooks-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let out = dir.join("out");
+    let log = dir.join("hooks.log");
+    let hooks = Hooks {
+        lap_start: Some(format!(
+            "echo \"$POMODORO_EVENT $POMODORO_PHASE $POMODORO_LAP $POMODORO_LOOP $POMODORO_DURATION\" >> {}",
+            out.display()
+        )),
+        pause: Some("echo complaining >&2; exit 3".to_owned()),
+        ..Hooks::default()
+    };
+
+    let worker = common::fake_worker();
+    let runner = HookRunner::spawn(hooks, Some(log.clone()), worker.handle());
+    worker.handle().call(Command::Start);
+    worker.handle().call(Command::Pause);
+    drop(worker);
+    drop(runner);
+
+    assert_eq!(fs::read_to_string(&out).unwrap(), "lap_start lap 1 1 1500\n");
+    let log = fs::read_to_string(&log).unwrap();
+    assert!(log.contains("complaining"), "{log}");
+    assert!(log.contains("pause: failed"), "{log}");
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,128 @@
+#![cfg(feature = "http")]
+
+mod common;
+
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+    time::Duration,
+};
+use pomodoro_rs::{
+    http::Server,
+    timer::Config,
+    worker::Command,
+};
+use tungstenite::client::IntoClientRequest;
+use common::fake_worker;
+
+/// One request on a fresh connection; the status code and body.
+fn call(addr: SocketAddr, method: &str, path: &str, body: &str) -> (u16, String) {
+    call_with(addr, method, path, &format!("Host: localhost:{}\r\n", addr.port()), body)
+}
+
+/// Like [`call`], with `headers` (each ending in CRLF) instead of the usual.
+fn call_with(addr: SocketAddr, method: &str, path: &str, headers: &str, body: &str) -> (u16, String) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(
+        stream,
+        "{method} {path} HTTP/1.1\r\n{headers}Connection: close\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+    )
+    .unwrap();
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply).unwrap();
+    let code = reply.split(' ').nth(1).unwrap().parse().unwrap();
+    let body = reply.split_once("\r\n\r\n").unwrap().1.to_owned();
+    (code, body)
+}
+
+#[test]
+fn routes_map_to_requests() {
+    let worker = fake_worker();
+    let server = Server::bind(0, worker.handle()).unwrap();
+    let addr = server.addr().unwrap();
+    assert!(addr.ip().is_loopback());
+
+    let (code, body) = call(addr, "GET", "/status", "");
+    assert_eq!(code, 200);
+    assert!(body.contains(r#""running":false"#), "{body}");
+
+    assert_eq!(call(addr, "POST", "/pause", "").0, 409);
+    assert_eq!(call(addr, "POST", "/start", "").0, 200);
+    assert!(worker.status().running);
+
+    assert_eq!(call(addr, "PUT", "/config", r#"{"lap":"1h30m"}"#).0, 200);
+    assert_eq!(worker.status().config.lap, Duration::from_secs(90 * 60));
+    // the old name still takes minutes
+    assert_eq!(call(addr, "PUT", "/config", r#"{"lap_dur_min":50}"#).0, 200);
+    assert_eq!(worker.status().config.lap, Duration::from_secs(50 * 60));
+    assert_eq!(call(addr, "PUT", "/config", r#"{"lap":"25h"}"#).0, 409);
+    assert_eq!(call(addr, "PUT", "/config", r#"{"lap_minutes":50}"#).0, 400);
+
+    assert_eq!(call(addr, "GET", "/start", "").0, 405);
+    assert_eq!(call(addr, "GET", "/nowhere", "").0, 404);
+}
+
+#[test]
+fn the_websocket_pushes_ticks_and_phase_changes() {
+    let worker = fake_worker();
+    let server = Server::bind(0, worker.handle()).unwrap();
+    let (mut socket, _) = tungstenite::connect(format!("ws://{}/events", server.addr().unwrap())).unwrap();
+
+    let first = socket.read().unwrap();
+    assert!(first.to_text().unwrap().contains(r#""event":"tick""#));
+
+    worker.handle().call(Command::Start);
+    worker.handle().call(Command::Skip);
+    let changed = (0..10)
+        .map(|_| socket.read().unwrap().to_text().unwrap().to_owned())
+        .find(|text| text.contains(r#""event":"phase_changed""#))
+        .unwrap();
+    assert!(changed.contains(r#""ended":"lap""#) && changed.contains(r#""next":"rest_lap""#), "{changed}");
+}
+
+#[test]
+fn other_hosts_are_refused() {
+    let worker = fake_worker();
+    let server = Server::bind(0, worker.handle()).unwrap();
+    let addr = server.addr().unwrap();
+    let port = addr.port();
+
+    // a rebound name pointing at 127.0.0.1
+    let (code, _) = call_with(addr, "POST", "/start", &format!("Host: evil.example:{port}\r\n"), "");
+    assert_eq!(code, 403);
+    assert_eq!(call_with(addr, "GET", "/status", "", "").0, 403);
+    assert_eq!(call_with(addr, "GET", "/status", "Host: localhost:1\r\n", "").0, 403);
+    assert!(!worker.status().running);
+
+    assert_eq!(call_with(addr, "GET", "/status", &format!("Host: 127.0.0.1:{port}\r\n"), "").0, 200);
+}
+
+#[test]
+fn other_origins_are_refused() {
+    let worker = fake_worker();
+    let server = Server::bind(0, worker.handle()).unwrap();
+    let addr = server.addr().unwrap();
+    let port = addr.port();
+
+    let from = |origin: &str| format!("Host: localhost:{port}\r\nOrigin: {origin}\r\n");
+    assert_eq!(call_with(addr, "POST", "/start", &from("https://evil.example"), "").0, 403);
+    assert_eq!(call_with(addr, "PUT", "/config", &from("null"), r#"{"lap":"1m"}"#).0, 403);
+    assert!(!worker.status().running);
+    assert_eq!(worker.status().config, Config::default());
+
+    assert_eq!(call_with(addr, "POST", "/start", &from(&format!("http://localhost:{port}")), "").0, 200);
+    assert!(worker.status().running);
+}
+
+#[test]
+fn the_websocket_refuses_other_origins() {
+    let worker = fake_worker();
+    let server = Server::bind(0, worker.handle()).unwrap();
+    let mut request = format!("ws://{}/events", server.addr().unwrap()).into_client_request().unwrap();
+    request.headers_mut().insert("Origin", "https://evil.example".parse().unwrap());
+    match tungstenite::connect(request) {
+        Err(tungstenite::Error::Http(response)) => assert_eq!(response.status(), 403),
+        other => panic!("expected a refusal, got {:?}", other.map(|_| ())),
+    }
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use std::time::Duration;
+use pomodoro_rs::{
+    ipc::{Request, Server, answers, handle_request},
+    timer::{Config, RunState},
+};
+use common::fake_worker;
+
+#[test]
+fn requests_drive_the_timer() {
+    let worker = fake_worker();
+    let handle = worker.handle();
+
+    let response = handle_request(&handle, Request::Start);
+    assert!(response.ok);
+    let status = response.status.unwrap();
+    assert!(status.running && !status.paused);
+    assert_eq!(status.remaining_sec, 25 * 60);
+
+    let status = handle_request(&handle, Request::Skip).status.unwrap();
+    assert_eq!(status.phase, RunState::RestLap);
+    assert_eq!(status.lap, 1);
+
+    assert!(handle_request(&handle, Request::Resume).ok);
+    assert!(handle_request(&handle, Request::Pause).ok);
+    assert!(handle_request(&handle, Request::Stop).ok);
+    assert!(!handle_request(&handle, Request::Status).status.unwrap().running);
+}
+
+#[test]
+fn pointless_requests_are_refused() {
+    let worker = fake_worker();
+    let handle = worker.handle();
+    for request in [Request::Pause, Request::Resume, Request::Stop, Request::Skip] {
+        let response = handle_request(&handle, request.clone());
+        assert!(!response.ok, "{request:?} on an idle timer");
+        assert!(response.error.is_some());
+    }
+    handle_request(&handle, Request::Start);
+    assert!(!handle_request(&handle, Request::Start).ok);
+    assert!(!handle_request(&handle, Request::Resume).ok);
+}
+
+#[test]
+fn extends_past_the_longest_phase_are_refused() {
+    let worker = fake_worker();
+    let handle = worker.handle();
+    handle_request(&handle, Request::Start);
+    assert!(!handle_request(&handle, Request::Extend { seconds: u64::MAX }).ok);
+    assert!(!handle_request(&handle, Request::Extend { seconds: 24 * 3600 }).ok);
+
+    let status = handle_request(&handle, Request::Extend { seconds: 5 * 60 }).status.unwrap();
+    assert_eq!(status.planned_sec, 30 * 60);
+    assert!(handle_request(&handle, Request::Pause).ok, "the worker is still alive");
+}
+
+#[test]
+fn set_config_patches_and_validates() {
+    let worker = fake_worker();
+    let handle = worker.handle();
+    let patch = |lap, laps_per_loop| Request::SetConfig { lap, rest_lap: None, rest_loop: None, laps_per_loop };
+    let lap = Duration::from_secs(90 * 60);
+
+    let status = handle_request(&handle, patch(Some(lap), None)).status.unwrap();
+    assert_eq!(status.config, Config { lap, ..Config::default() });
+    assert_eq!(status.remaining_sec, 90 * 60);
+
+    assert!(!handle_request(&handle, patch(None, Some(0))).ok);
+    assert!(!handle_request(&handle, patch(Some(Duration::ZERO), None)).ok);
+    assert_eq!(handle.status().config.lap, lap);
+}
+
+#[test]
+fn a_second_server_is_turned_away() {
+    let path = std::env::temp_dir().join(format!("pomodoro-ipc-{}.sock", std::process::id()));
+    let worker = fake_worker();
+    assert!(!answers(&path));
+    let server = Server::bind(&path, worker.handle()).unwrap();
+    assert!(answers(&path));
+    let err = Server::bind(&path, worker.handle()).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::AddrInUse);
+    drop(server);
+    assert!(!answers(&path));
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use std::{sync::Arc, thread, time::Duration};
+use pomodoro_rs::{
+    notify::{Action, Announcer, Kind, Mock, Notifications},
+    timer::RunState,
+    worker::Command,
+};
+use common::{eventually, fake_worker};
+
+#[test]
+fn every_transition_has_its_own_notification() {
+    assert_eq!(Kind::of(RunState::LAP, RunState::RestLap), Kind::LapEnd);
+    assert_eq!(Kind::of(RunState::LAP, RunState::RestLoop), Kind::LongBreak);
+    assert_eq!(Kind::of(RunState::RestLap, RunState::LAP), Kind::BreakOver);
+    assert_eq!(Kind::of(RunState::RestLoop, RunState::LAP), Kind::LoopComplete);
+}
+
+#[test]
+fn lap_end_offers_the_rest() {
+    let worker = fake_worker();
+    let mock = Arc::new(Mock::default());
+    let announcer = Announcer::spawn(Notifications::default(), mock.clone(), worker.handle());
+
+    worker.handle().call(Command::Start);
+    worker.handle().call(Command::Skip);
+    eventually("the lap end notification", || mock.messages().len() == 1);
+    let message = &mock.messages()[0];
+    assert_eq!(message.kind, Kind::LapEnd);
+    assert_eq!(message.body, "Time out! Please check your tomato!");
+    assert_eq!(message.actions, [Action::StartBreak, Action::Snooze, Action::SkipBreak]);
+
+    // snoozing leaves the rest waiting
+    assert!(mock.click(0, Action::Snooze));
+    assert!(!mock.click(0, Action::StartBreak), "a notification is only acted on once");
+    thread::sleep(Duration::from_millis(300));
+    let status = worker.status();
+    assert!(status.pause && status.run_state == RunState::RestLap);
+    drop(worker);
+    drop(announcer);
+}
+
+#[test]
+fn actions_drive_the_timer() {
+    let worker = fake_worker();
+    let mock = Arc::new(Mock::default());
+    let announcer = Announcer::spawn(Notifications::default(), mock.clone(), worker.handle());
+
+    worker.handle().call(Command::Start);
+    worker.handle().call(Command::Skip);
+    eventually("the lap end notification", || mock.messages().len() == 1);
+    assert!(mock.click(0, Action::StartBreak));
+    eventually("the rest to start", || {
+        let status = worker.status();
+        status.run_state == RunState::RestLap && !status.pause
+    });
+
+    worker.handle().call(Command::Skip);
+    worker.handle().call(Command::Skip);
+    eventually("the second lap end", || mock.messages().len() == 3);
+    assert_eq!(mock.messages()[1].kind, Kind::BreakOver);
+    assert!(mock.messages()[1].actions.is_empty());
+    assert!(mock.click(2, Action::SkipBreak));
+    eventually("the next lap", || {
+        let status = worker.status();
+        status.run_state == RunState::LAP && !status.pause
+    });
+    drop(worker);
+    drop(announcer);
+}
+
+#[test]
+fn old_notifications_leave_the_next_rest_alone() {
+    let worker = fake_worker();
+    let mock = Arc::new(Mock::default());
+    let announcer = Announcer::spawn(Notifications::default(), mock.clone(), worker.handle());
+
+    worker.handle().call(Command::Start);
+    worker.handle().call(Command::Skip);
+    eventually("the first lap end", || mock.messages().len() == 1);
+    // the rest is started and got through without the notification
+    worker.handle().call(Command::Resume);
+    worker.handle().call(Command::Skip);
+    worker.handle().call(Command::Skip);
+    eventually("the second lap end", || mock.messages().len() == 3);
+
+    assert!(mock.click(0, Action::SkipBreak));
+    thread::sleep(Duration::from_millis(300));
+    let status = worker.status();
+    assert!(status.pause && status.run_state == RunState::RestLap && status.cur_lap == 2);
+
+    assert!(mock.click(2, Action::StartBreak));
+    eventually("the second rest to start", || {
+        let status = worker.status();
+        status.run_state == RunState::RestLap && !status.pause
+    });
+    drop(worker);
+    drop(announcer);
+}
+
+#[test]
+fn turned_off_notifications_are_not_shown() {
+    let worker = fake_worker();
+    let mock = Arc::new(Mock::default());
+    let mut notifications = Notifications::default();
+    notifications.lap_end.enabled = false;
+    let announcer = Announcer::spawn(notifications, mock.clone(), worker.handle());
+
+    worker.handle().call(Command::Start);
+    worker.handle().call(Command::Skip);
+    drop(worker);
+    drop(announcer);
+    assert!(mock.messages().is_empty());
+}
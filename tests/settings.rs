@@ -0,0 +1,130 @@
+mod common;
+
+use std::{fs, sync::Arc, time::Duration};
+use pomodoro_rs::{
+    settings::{Error, SCHEMA_VERSION, Settings},
+    sound::{Mock, Player, Sounds},
+    timer::{Config, RunState},
+    worker::Command,
+};
+use common::fake_worker;
+
+#[test]
+fn version_1_minutes_still_load() {
+    let dir = std::env::temp_dir().join(format!("pomodoro-settings-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config.toml");
+    fs::write(&path, "version = 1\n\n[timer]\nlap_dur_min = 50\nrest_lap_min = 10\n").unwrap();
+
+    let settings = Settings::load_from(&path).unwrap();
+    assert_eq!(settings.version, SCHEMA_VERSION);
+    assert_eq!(settings.timer.lap, Duration::from_secs(50 * 60));
+    assert_eq!(settings.timer.rest_lap, Duration::from_secs(10 * 60));
+    assert_eq!(settings.timer.rest_loop, Duration::from_secs(30 * 60));
+
+    // written back the new way
+    settings.save_to(&path).unwrap();
+    let text = fs::read_to_string(&path).unwrap();
+    assert!(text.contains("lap = \"50m\""), "{text}");
+    assert_eq!(Settings::load_from(&path).unwrap(), settings);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn durations_can_be_seconds_or_hours() {
+    let mut settings = Settings::default();
+    settings.timer.lap = Duration::from_secs(90 * 60);
+    settings.timer.rest_lap = Duration::from_secs(45);
+    assert!(settings.validate().is_ok());
+    settings.timer.rest_loop = Duration::ZERO;
+    assert!(settings.validate().is_err());
+    settings.timer.rest_loop = Duration::from_secs(25 * 3600);
+    assert!(settings.validate().is_err());
+}
+
+#[test]
+fn sequences_load_from_toml() {
+    let dir = std::env::temp_dir().join(format!("pomodoro-sequence-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config.toml");
+    fs::write(
+        &path,
+        "[[sequence]]\nlabel = \"focus\"\nlength = \"50m\"\n\n\
+         [[sequence]]\nlabel = \"long\"\nlength = 30\nkind = \"rest_loop\"\n",
+    )
+    .unwrap();
+
+    let mut settings = Settings::load_from(&path).unwrap();
+    assert_eq!(settings.sequence.len(), 2);
+    assert_eq!((settings.sequence[0].length, settings.sequence[0].kind), (Duration::from_secs(50 * 60), RunState::LAP));
+    assert_eq!(settings.sequence[1].kind, RunState::RestLoop);
+    assert_eq!(settings.new_timer().status().label, "focus");
+
+    // something has to be a lap
+    settings.sequence.remove(0);
+    assert!(settings.validate().is_err());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn broken_files_are_refused_and_can_be_backed_up() {
+    let dir = std::env::temp_dir().join(format!("pomodoro-broken-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config.toml");
+
+    fs::write(&path, "[timer\nlap = \"25m\"\n").unwrap();
+    assert!(matches!(Settings::load_from(&path), Err(Error::Parse(..))));
+    fs::write(&path, format!("version = {}\n", SCHEMA_VERSION + 1)).unwrap();
+    assert!(matches!(Settings::load_from(&path), Err(Error::Version(_))));
+    fs::write(&path, "[timer]\nlaps_per_loop = 0\n\n[hooks]\nlap_start = \"true\"\n").unwrap();
+    assert!(matches!(Settings::load_from(&path), Err(Error::Invalid(_))));
+
+    // saving the defaults over it leaves the hooks in the backup
+    let backup = Settings::back_up_at(&path).unwrap().unwrap();
+    assert_eq!(backup, dir.join("config.toml.bak"));
+    assert!(fs::read_to_string(&backup).unwrap().contains("lap_start"));
+    Settings::default().save_to(&path).unwrap();
+    assert_eq!(Settings::load_from(&path).unwrap(), Settings::default());
+    assert_eq!(Settings::back_up_at(&dir.join("missing.toml")).unwrap(), None);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn a_missing_file_gives_the_defaults() {
+    let path = std::env::temp_dir().join(format!("pomodoro-missing-{}/config.toml", std::process::id()));
+    assert_eq!(Settings::load_from(&path).unwrap(), Settings::default());
+}
+
+#[test]
+fn applied_settings_are_used_even_when_they_cannot_be_saved() {
+    let dir = std::env::temp_dir().join(format!("pomodoro-apply-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config.toml");
+    fs::write(&path, "[timer\n").unwrap();
+    let worker = fake_worker();
+    let player = Player::spawn(Sounds::default(), Arc::new(Mock::default()), worker.handle());
+    let apply = |settings: &Settings, path, broken| {
+        let applied = settings.apply_to(path, broken, &worker.handle(), &player);
+        // anything sent before this has been carried out once it returns
+        worker.handle().call(Command::Pause);
+        applied
+    };
+
+    let mut settings = Settings::default();
+    settings.timer.laps_per_loop = 0;
+    assert!(matches!(apply(&settings, Some(&path), true), Err(Error::Invalid(_))));
+    assert_eq!(worker.status().config, Config::default());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "[timer\n");
+
+    settings.timer.laps_per_loop = 2;
+    assert_eq!(apply(&settings, Some(&path), true).unwrap(), Some(dir.join("config.toml.bak")));
+    assert_eq!(worker.status().config.laps_per_loop, 2);
+    assert_eq!(Settings::load_from(&path).unwrap(), settings);
+
+    settings.timer.laps_per_loop = 3;
+    assert!(matches!(apply(&settings, None, false), Err(Error::NotSaved(_))));
+    assert_eq!(worker.status().config.laps_per_loop, 3);
+    drop(worker);
+    drop(player);
+    fs::remove_dir_all(&dir).unwrap();
+}
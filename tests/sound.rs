@@ -0,0 +1,78 @@
+mod common;
+
+use std::{sync::Arc, thread, time::Duration};
+use pomodoro_rs::{
+    clock::FakeClock,
+    sound::{Cue, Mock, Player, Source, Sounds},
+    timer::RunState,
+    worker::Command,
+};
+use common::{eventually, fake_worker, worker_on};
+
+fn sources(mock: &Mock) -> Vec<Source> {
+    mock.played().into_iter().map(|(source, _)| source).collect()
+}
+
+#[test]
+fn sounds_are_bundled_files_or_off() {
+    assert_eq!(Source::parse("bell"), Some(Source::Bundled("bell")));
+    assert_eq!(Source::parse("/tmp/gong.ogg"), Some(Source::File("/tmp/gong.ogg".into())));
+    assert_eq!(Source::parse("off"), None);
+    assert!(Source::Bundled("tick").bytes().unwrap().starts_with(b"RIFF"));
+
+    let mut sounds = Sounds::default();
+    assert_eq!(sounds.get(Cue::ended(RunState::RestLoop)), Some(Source::Bundled("gong")));
+    assert!(sounds.check().is_ok());
+    sounds.warning = "chmie".to_owned();
+    assert!(sounds.check().unwrap_err().contains("sounds.warning"));
+    sounds.warning = "ping".to_owned();
+    sounds.volume = 101;
+    assert!(sounds.check().is_err());
+}
+
+#[test]
+fn the_ends_of_phases_are_heard() {
+    let worker = fake_worker();
+    let mock = Arc::new(Mock::default());
+    let sounds = Sounds { volume: 50, rest_end: "/tmp/back-to-work.ogg".to_owned(), ..Sounds::default() };
+    let player = Player::spawn(sounds, mock.clone(), worker.handle());
+
+    worker.handle().call(Command::Start);
+    worker.handle().call(Command::Skip);
+    eventually("the lap end sound", || mock.played().len() == 1);
+    assert_eq!(mock.played()[0], (Source::Bundled("bell"), 0.5));
+
+    player.set_volume(100);
+    worker.handle().call(Command::Resume);
+    worker.handle().call(Command::Skip);
+    eventually("the rest end sound", || mock.played().len() == 2);
+    assert_eq!(mock.played()[1], (Source::File("/tmp/back-to-work.ogg".into()), 1.0));
+    drop(worker);
+    drop(player);
+}
+
+#[test]
+fn laps_tick_once_a_second() {
+    let clock = Arc::new(FakeClock::new());
+    let worker = worker_on(clock.clone());
+    let mock = Arc::new(Mock::default());
+    let player = Player::spawn(Sounds { ticking: true, ..Sounds::default() }, mock.clone(), worker.handle());
+
+    worker.handle().call(Command::Start);
+    eventually("the first tick", || mock.played().len() == 1);
+    clock.advance(Duration::from_secs(1));
+    eventually("the second tick", || mock.played().len() == 2);
+    assert_eq!(sources(&mock), [Source::Bundled("tick"), Source::Bundled("tick")]);
+
+    // nothing while paused or turned off
+    worker.handle().call(Command::Pause);
+    clock.advance(Duration::from_secs(1));
+    thread::sleep(Duration::from_millis(300));
+    player.set_ticking(false);
+    worker.handle().call(Command::Resume);
+    clock.advance(Duration::from_secs(1));
+    thread::sleep(Duration::from_millis(300));
+    assert_eq!(mock.played().len(), 2);
+    drop(worker);
+    drop(player);
+}
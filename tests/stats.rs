@@ -0,0 +1,64 @@
+use chrono::{Days, NaiveDate};
+use pomodoro_rs::{
+    history::{Entry, Outcome},
+    stats::Stats,
+    timer::RunState,
+};
+
+fn entry(phase: RunState, days_ago: u64, actual_min: u64, outcome: Outcome) -> Entry {
+    Entry {
+        phase,
+        lap: 1,
+        cycle: 1,
+        started_at: days_ago,
+        ended_at: days_ago,
+        planned_sec: 25 * 60,
+        extended_sec: 0,
+        actual_sec: actual_min * 60,
+        outcome,
+    }
+}
+
+#[test]
+fn counts_laps_by_day() {
+    // a Friday, so the week began four days earlier
+    let today = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+    let entries = [
+        entry(RunState::LAP, 5, 25, Outcome::Completed),
+        entry(RunState::LAP, 3, 25, Outcome::Completed),
+        entry(RunState::LAP, 1, 25, Outcome::Completed),
+        entry(RunState::RestLap, 0, 5, Outcome::Completed),
+        entry(RunState::LAP, 0, 25, Outcome::Completed),
+        entry(RunState::LAP, 0, 10, Outcome::Aborted),
+        entry(RunState::LAP, 0, 25, Outcome::Completed),
+    ];
+    // ended_at holds how many days back the entry is
+    let stats = Stats::compute(&entries, today, 7, |days_ago| today.checked_sub_days(Days::new(days_ago)));
+
+    assert_eq!(stats.completed_today, 2);
+    assert_eq!(stats.completed_this_week, 4);
+    assert_eq!(stats.focus_minutes, 5 * 25 + 10);
+    assert_eq!(stats.completion_rate, Some(5.0 / 6.0));
+    assert_eq!(stats.longest_streak, 2);
+    let per_day: Vec<_> = stats.per_day.iter().map(|(_, count)| *count).collect();
+    assert_eq!(per_day, [0, 1, 0, 1, 0, 1, 2]);
+    assert_eq!(stats.per_day.last().map(|(date, _)| *date), Some(today));
+}
+
+#[test]
+fn focus_time_adds_up_before_rounding() {
+    let today = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+    let mut entries = vec![Entry { actual_sec: 90, ..entry(RunState::LAP, 0, 0, Outcome::Completed) }; 10];
+    entries.push(Entry { actual_sec: 30, ..entry(RunState::LAP, 0, 0, Outcome::Skipped) });
+    let stats = Stats::compute(&entries, today, 1, |_| Some(today));
+    assert_eq!(stats.focus_minutes, 15);
+}
+
+#[test]
+fn empty_history() {
+    let today = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+    let stats = Stats::compute(&[], today, 3, |_| None);
+    assert_eq!(stats.completion_rate, None);
+    assert_eq!(stats.longest_streak, 0);
+    assert_eq!(stats.per_day.len(), 3);
+}
@@ -0,0 +1,71 @@
+use pomodoro_rs::{
+    ipc::{Report, Response},
+    statusbar::{Format, fill, render},
+    timer::{Config, RunState},
+};
+
+fn report(running: bool, paused: bool) -> Report {
+    Report {
+        phase: RunState::LAP,
+        label: "focus".to_owned(),
+        running,
+        paused,
+        remaining_sec: 15 * 60,
+        lap: 2,
+        laps_per_loop: 4,
+        cycle: 1,
+        planned_sec: 25 * 60,
+        config: Config::default(),
+    }
+}
+
+#[test]
+fn each_format() {
+    let running = report(true, false);
+    let line = render(Format::Json, None, Some(&running));
+    // the same answer `pomodoro_rs status` gives, with or without --follow
+    let response: Response = serde_json::from_str(&line).unwrap();
+    assert!(response.ok && response.error.is_none());
+    assert_eq!(response.status, Some(running.clone()));
+
+    let waybar: serde_json::Value = serde_json::from_str(&render(Format::Waybar, None, Some(&running))).unwrap();
+    assert_eq!(waybar["text"], "15:00");
+    assert_eq!(waybar["tooltip"], "focus Lap: 2/4, Loop 1");
+    assert_eq!(waybar["class"], "lap");
+    assert_eq!(waybar["percentage"], 40);
+
+    assert_eq!(render(Format::Polybar, None, Some(&running)), "%{F#e06c75}15:00 2/4%{F-}");
+    assert_eq!(render(Format::Plain, None, Some(&running)), "lap 15:00 2/4 loop 1");
+
+    let paused = report(true, true);
+    assert_eq!(render(Format::Plain, None, Some(&paused)), "lap 15:00 2/4 loop 1 (paused)");
+    assert_eq!(render(Format::Polybar, None, Some(&paused)), "%{F#888888}15:00 2/4%{F-}");
+    let waybar: serde_json::Value = serde_json::from_str(&render(Format::Waybar, None, Some(&paused))).unwrap();
+    assert_eq!(waybar["class"], "paused");
+    assert_eq!(waybar["tooltip"], "focus Lap: 2/4, Loop 1 (paused)");
+}
+
+#[test]
+fn no_timer_prints_an_empty_line() {
+    for format in [Format::Json, Format::Polybar, Format::Plain] {
+        assert_eq!(render(format, None, None), "", "{format:?}");
+    }
+    assert_eq!(render(Format::Plain, Some("{remaining}"), None), "");
+    let waybar: serde_json::Value = serde_json::from_str(&render(Format::Waybar, None, None)).unwrap();
+    assert_eq!(waybar["text"], "");
+    assert_eq!(waybar["class"], "offline");
+}
+
+#[test]
+fn templates_fill_in_each_field_once() {
+    let running = report(true, false);
+    assert_eq!(
+        fill("{phase} {label} {state} {remaining} {lap}/{laps} {loop}", &running),
+        "lap focus running 15:00 2/4 1"
+    );
+    assert_eq!(render(Format::Waybar, Some("{state}"), Some(&report(false, true))), "stopped");
+
+    // a label is shown as written, never expanded itself
+    let sneaky = Report { label: "{remaining} {nope".to_owned(), ..running };
+    assert_eq!(fill("{label}|{unknown}|{", &sneaky), "{remaining} {nope|{unknown}|{");
+}
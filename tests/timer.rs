@@ -0,0 +1,555 @@
+use std::{fs, sync::Arc, time::Duration};
+use pomodoro_rs::{
+    checkpoint::Checkpoint,
+    clock::FakeClock,
+    history::{Entry, History, Outcome},
+    timer::{Config, Event, RunState, Step, Timer, Warnings},
+};
+
+fn minutes(min: u64) -> Duration {
+    Duration::from_secs(min * 60)
+}
+
+fn fake_timer() -> (Arc<FakeClock>, Timer) {
+    let clock = Arc::new(FakeClock::new());
+    let timer = Timer::with_clock(Config::default(), clock.clone());
+    (clock, timer)
+}
+
+fn drain(timer: &mut Timer) -> Vec<Event> {
+    std::iter::from_fn(|| timer.next_event()).collect()
+}
+
+fn finished(timer: &mut Timer) -> Vec<Entry> {
+    drain(timer)
+        .into_iter()
+        .filter_map(|event| match event {
+            Event::Finished(entry) => Some(entry),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn full_loop() {
+    let (clock, mut timer) = fake_timer();
+    for lap in 1..=4 {
+        timer.start();
+        clock.advance(minutes(25));
+        timer.tick();
+        assert!(timer.is_paused(), "lap {lap} should wait for the user before resting");
+        if lap < 4 {
+            assert_eq!(timer.run_state(), RunState::RestLap);
+            assert_eq!(timer.cur_lap(), lap);
+            assert_eq!(timer.remaining_sec(), 5 * 60);
+
+            timer.start();
+            clock.advance(minutes(5));
+            timer.tick();
+            assert_eq!(timer.run_state(), RunState::LAP);
+            assert!(!timer.is_paused(), "rest rolls straight into the next lap");
+            timer.pause();
+        }
+    }
+    assert_eq!(timer.run_state(), RunState::RestLoop);
+    assert_eq!(timer.cur_lap(), 0);
+    assert_eq!(timer.cur_loop(), 1);
+    assert_eq!(timer.remaining_sec(), 30 * 60);
+
+    timer.start();
+    clock.advance(minutes(30));
+    timer.tick();
+    assert_eq!(timer.run_state(), RunState::LAP);
+    assert_eq!(timer.remaining_sec(), 25 * 60);
+
+    let ended: Vec<_> = drain(&mut timer)
+        .into_iter()
+        .filter_map(|event| match event {
+            Event::PhaseEnded { ended, .. } => Some(ended),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(ended, [
+        RunState::LAP, RunState::RestLap,
+        RunState::LAP, RunState::RestLap,
+        RunState::LAP, RunState::RestLap,
+        RunState::LAP, RunState::RestLoop,
+    ]);
+}
+
+#[test]
+fn auto_start_is_up_to_each_phase() {
+    let clock = Arc::new(FakeClock::new());
+    let config = Config {
+        laps_per_loop: 2,
+        auto_start_breaks: true,
+        auto_start_laps: false,
+        auto_start_loop: false,
+        ..Config::default()
+    };
+    let mut timer = Timer::with_clock(config, clock.clone());
+    timer.start();
+    clock.advance(minutes(25 + 5));
+    timer.tick();
+    assert_eq!(timer.run_state(), RunState::LAP);
+    assert!(timer.is_paused(), "the rest started by itself, the lap after it waits");
+
+    timer.start();
+    clock.advance(minutes(25 + 30));
+    timer.tick();
+    assert_eq!(timer.run_state(), RunState::LAP);
+    assert_eq!(timer.cur_loop(), 1);
+    assert!(timer.is_paused(), "the next loop waits");
+    assert_eq!(timer.remaining_sec(), 25 * 60);
+}
+
+#[test]
+fn partial_seconds_carry_over() {
+    let (clock, mut timer) = fake_timer();
+    timer.start();
+    clock.advance(Duration::from_millis(600));
+    timer.tick();
+    assert_eq!(timer.remaining_sec(), 25 * 60);
+    clock.advance(Duration::from_millis(600));
+    timer.tick();
+    assert_eq!(timer.remaining_sec(), 25 * 60 - 1);
+}
+
+#[test]
+fn paused_time_is_not_counted() {
+    let (clock, mut timer) = fake_timer();
+    timer.start();
+    clock.advance(minutes(10));
+    timer.tick();
+    timer.pause();
+    clock.advance(minutes(60));
+    timer.tick();
+    assert_eq!(timer.remaining_sec(), 15 * 60);
+
+    timer.start();
+    clock.advance(minutes(1));
+    timer.tick();
+    assert_eq!(timer.remaining_sec(), 14 * 60);
+    assert_eq!(drain(&mut timer), [
+        Event::Started(RunState::LAP),
+        Event::Paused(RunState::LAP),
+        Event::Started(RunState::LAP),
+    ]);
+
+    timer.stop();
+    let entries = finished(&mut timer);
+    assert_eq!(entries.len(), 1);
+    let entry = entries[0];
+    assert_eq!(entry.outcome, Outcome::Aborted);
+    assert_eq!(entry.planned_sec, 25 * 60);
+    assert_eq!(entry.actual_sec, 11 * 60);
+    assert_eq!(entry.ended_at - entry.started_at, 71 * 60);
+}
+
+#[test]
+fn rest_overrun_carries_into_next_lap() {
+    let (clock, mut timer) = fake_timer();
+    timer.start();
+    clock.advance(minutes(25));
+    timer.tick();
+    timer.start();
+    clock.advance(minutes(7));
+    timer.tick();
+    assert_eq!(timer.run_state(), RunState::LAP);
+    assert_eq!(timer.remaining_sec(), 23 * 60);
+}
+
+#[test]
+fn stop_resets_but_keeps_config() {
+    let clock = Arc::new(FakeClock::new());
+    let config = Config { lap: minutes(50), rest_lap: minutes(10), rest_loop: minutes(30), laps_per_loop: 3, ..Config::default() };
+    let mut timer = Timer::with_clock(config, clock.clone());
+    timer.start();
+    clock.advance(minutes(50));
+    timer.tick();
+    timer.stop();
+    assert!(!timer.is_running());
+    assert_eq!(timer.run_state(), RunState::LAP);
+    assert_eq!(timer.cur_lap(), 0);
+    assert_eq!(timer.remaining_sec(), 50 * 60);
+    assert_eq!(timer.config(), config);
+}
+
+#[test]
+fn late_ticks_do_not_drift() {
+    let (clock, mut timer) = fake_timer();
+    timer.start();
+    for _ in 0..25 * 60 - 1 {
+        clock.advance(Duration::from_millis(999));
+        timer.tick();
+        clock.advance(Duration::from_millis(1));
+    }
+    timer.tick();
+    assert_eq!(timer.remaining(), Duration::from_secs(1));
+    clock.advance(Duration::from_secs(1));
+    timer.tick();
+    assert_eq!(timer.run_state(), RunState::RestLap);
+}
+
+#[test]
+fn suspend_counts_against_the_phase() {
+    let (clock, mut timer) = fake_timer();
+    timer.start();
+    clock.advance(minutes(10));
+    timer.tick();
+    clock.suspend(minutes(20));
+    timer.tick();
+    assert_eq!(timer.run_state(), RunState::RestLap);
+    let events = drain(&mut timer);
+    assert!(events.contains(&Event::Suspended(minutes(20))));
+}
+
+#[test]
+fn long_suspend_ends_only_the_current_phase() {
+    let (clock, mut timer) = fake_timer();
+    timer.start();
+    clock.advance(minutes(25));
+    timer.tick();
+    drain(&mut timer);
+    timer.start();
+    clock.advance(minutes(1));
+    timer.tick();
+    clock.suspend(minutes(10 * 60));
+    timer.tick();
+    assert_eq!(timer.run_state(), RunState::LAP);
+    assert!(timer.is_paused(), "the next lap waits for whoever comes back");
+    assert_eq!(timer.remaining(), minutes(25));
+    let entries = finished(&mut timer);
+    assert_eq!(entries.len(), 1);
+    assert_eq!((entries[0].phase, entries[0].outcome), (RunState::RestLap, Outcome::Completed));
+    assert_eq!(entries[0].actual_sec, 5 * 60);
+}
+
+#[test]
+fn short_suspend_while_resting() {
+    let (clock, mut timer) = fake_timer();
+    timer.start();
+    clock.advance(minutes(25));
+    timer.tick();
+    timer.start();
+    clock.suspend(minutes(2));
+    timer.tick();
+    assert_eq!(timer.run_state(), RunState::RestLap);
+    assert_eq!(timer.remaining(), minutes(3));
+}
+
+#[test]
+fn suspend_while_paused_is_ignored() {
+    let (clock, mut timer) = fake_timer();
+    timer.start();
+    clock.advance(minutes(5));
+    timer.pause();
+    clock.suspend(minutes(60));
+    timer.start();
+    timer.tick();
+    assert_eq!(timer.remaining(), minutes(20));
+}
+
+#[test]
+fn laps_per_loop_is_honoured() {
+    for laps_per_loop in [1, 3, 6] {
+        let clock = Arc::new(FakeClock::new());
+        let config = Config { laps_per_loop, ..Config::default() };
+        let mut timer = Timer::with_clock(config, clock.clone());
+        for _ in 1..laps_per_loop {
+            timer.start();
+            clock.advance(minutes(25));
+            timer.tick();
+            assert_eq!(timer.run_state(), RunState::RestLap);
+            timer.skip();
+        }
+        timer.start();
+        clock.advance(minutes(25));
+        timer.tick();
+        assert_eq!(timer.run_state(), RunState::RestLoop, "{laps_per_loop} laps per loop");
+        assert_eq!(timer.cur_lap(), 0);
+        assert_eq!(timer.cur_loop(), 1);
+    }
+}
+
+#[test]
+fn phases_are_recorded() {
+    let (clock, mut timer) = fake_timer();
+    timer.start();
+    clock.advance(minutes(25));
+    timer.tick();
+    clock.advance(minutes(3));
+    timer.start();
+    clock.advance(minutes(2));
+    timer.skip();
+    clock.advance(minutes(25));
+    timer.tick();
+    timer.skip();
+    timer.stop();
+
+    let entries = finished(&mut timer);
+    let summary: Vec<_> = entries
+        .iter()
+        .map(|entry| (entry.phase, entry.lap, entry.cycle, entry.actual_sec / 60, entry.outcome))
+        .collect();
+    assert_eq!(summary, [
+        (RunState::LAP, 1, 1, 25, Outcome::Completed),
+        (RunState::RestLap, 1, 1, 2, Outcome::Skipped),
+        (RunState::LAP, 2, 1, 25, Outcome::Completed),
+        (RunState::RestLap, 2, 1, 0, Outcome::Skipped),
+    ]);
+    // the third lap was entered but never started, so stopping logs nothing
+    assert_eq!(entries[1].started_at, entries[0].ended_at + 3 * 60);
+    assert_eq!(entries[2].started_at, entries[1].ended_at);
+}
+
+#[test]
+fn checkpoint_resumes_against_the_saved_deadline() {
+    let (clock, mut timer) = fake_timer();
+    assert_eq!(timer.checkpoint(), None);
+    timer.start();
+    clock.advance(minutes(10));
+    timer.tick();
+    let checkpoint = timer.checkpoint().unwrap();
+
+    // the app is gone for five minutes
+    clock.advance(minutes(5));
+    let mut restored = Timer::with_clock(Config::default(), clock.clone());
+    restored.restore(&checkpoint);
+    assert!(restored.is_running() && restored.is_paused());
+    assert_eq!(restored.remaining(), minutes(10));
+
+    restored.start();
+    clock.advance(minutes(10));
+    restored.tick();
+    assert_eq!(restored.run_state(), RunState::RestLap);
+    let entries = finished(&mut restored);
+    assert_eq!(entries[0].started_at, checkpoint.phase_started.unwrap());
+    assert_eq!(entries[0].outcome, Outcome::Completed);
+}
+
+#[test]
+fn paused_checkpoint_keeps_its_time() {
+    let (clock, mut timer) = fake_timer();
+    timer.start();
+    clock.advance(minutes(25));
+    timer.tick();
+    timer.start();
+    clock.advance(minutes(2));
+    timer.pause();
+    let checkpoint = timer.checkpoint().unwrap();
+    assert_eq!(checkpoint.deadline_ms, None);
+
+    clock.advance(minutes(60));
+    let mut restored = Timer::with_clock(Config::default(), clock.clone());
+    restored.restore(&checkpoint);
+    assert_eq!(restored.run_state(), RunState::RestLap);
+    assert_eq!(restored.cur_lap(), 1);
+    assert_eq!(restored.remaining(), minutes(3));
+
+    let entry = checkpoint.abandoned().unwrap();
+    assert_eq!((entry.phase, entry.lap, entry.cycle), (RunState::RestLap, 1, 1));
+    assert_eq!(entry.actual_sec, 2 * 60);
+    assert_eq!(entry.outcome, Outcome::Aborted);
+}
+
+#[test]
+fn a_discarded_checkpoint_is_logged_once_and_gone() {
+    let (clock, mut timer) = fake_timer();
+    timer.start();
+    clock.advance(minutes(10));
+    timer.tick();
+    let checkpoint = timer.checkpoint().unwrap();
+
+    let dir = std::env::temp_dir().join(format!("pomodoro-discard-{}", std::process::id()));
+    let path = dir.join("session.json");
+    Checkpoint::store(&path, Some(&checkpoint)).unwrap();
+    let history = History::new(dir.join("history.jsonl"));
+    checkpoint.discard(&path, Some(&history));
+    assert_eq!(Checkpoint::load(&path), None);
+    let entries = history.load().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!((entries[0].outcome, entries[0].actual_sec), (Outcome::Aborted, 10 * 60));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn extend_pushes_the_deadline_and_is_recorded() {
+    let (clock, mut timer) = fake_timer();
+    timer.start();
+    clock.advance(minutes(20));
+    timer.extend(minutes(5));
+    assert_eq!(timer.remaining(), minutes(10));
+    assert_eq!(timer.checkpoint().unwrap().extended_sec, 5 * 60);
+    timer.pause();
+    timer.extend(minutes(1));
+    timer.start();
+    clock.advance(minutes(11));
+    timer.tick();
+    assert_eq!(timer.run_state(), RunState::RestLap);
+    let entry = finished(&mut timer)[0];
+    assert_eq!(entry.planned_sec, 31 * 60);
+    assert_eq!(entry.extended_sec, 6 * 60);
+    assert_eq!(entry.actual_sec, 31 * 60);
+
+    // the next phase starts over
+    timer.start();
+    timer.skip();
+    let entry = finished(&mut timer)[0];
+    assert_eq!((entry.extended_sec, entry.outcome), (0, Outcome::Skipped));
+}
+
+#[test]
+fn extend_ignores_amounts_that_overflow() {
+    let (_clock, mut timer) = fake_timer();
+    timer.start();
+    timer.extend(Duration::MAX);
+    assert_eq!(timer.remaining(), minutes(25));
+    timer.pause();
+    timer.extend(Duration::MAX);
+    assert_eq!(timer.remaining(), minutes(25));
+    assert_eq!(timer.checkpoint().unwrap().extended_sec, 0);
+}
+
+fn warnings(timer: &mut Timer) -> Vec<(RunState, u64)> {
+    drain(timer)
+        .into_iter()
+        .filter_map(|event| match event {
+            Event::Warning(phase, left) => Some((phase, left.as_secs())),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn warnings_come_once_before_the_end() {
+    let (clock, mut timer) = fake_timer();
+    timer.set_warnings(Warnings { lap_sec: vec![300, 60], rest_sec: vec![120] });
+    timer.start();
+    clock.advance(minutes(19));
+    timer.tick();
+    assert_eq!(warnings(&mut timer), []);
+
+    // one late tick can go past both at once
+    clock.advance(minutes(5) + Duration::from_secs(30));
+    timer.tick();
+    assert_eq!(warnings(&mut timer), [(RunState::LAP, 300), (RunState::LAP, 60)]);
+    clock.advance(Duration::from_secs(10));
+    timer.tick();
+    assert_eq!(warnings(&mut timer), []);
+
+    // running out is warning enough
+    clock.advance(minutes(1));
+    timer.tick();
+    assert_eq!(warnings(&mut timer), []);
+    timer.start();
+    clock.advance(minutes(3));
+    timer.tick();
+    assert_eq!(warnings(&mut timer), [(RunState::RestLap, 120)]);
+}
+
+#[test]
+fn warnings_survive_a_stop() {
+    let (clock, mut timer) = fake_timer();
+    timer.set_warnings(Warnings { lap_sec: vec![60], rest_sec: vec![] });
+    timer.start();
+    timer.stop();
+    timer.start();
+    clock.advance(minutes(24));
+    timer.tick();
+    assert_eq!(warnings(&mut timer), [(RunState::LAP, 60)]);
+}
+
+fn step(label: &str, min: u64, kind: RunState) -> Step {
+    Step { label: label.to_owned(), length: minutes(min), kind }
+}
+
+#[test]
+fn sequences_go_round_step_by_step() {
+    let (clock, mut timer) = fake_timer();
+    timer.set_sequence(vec![
+        step("focus", 50, RunState::LAP),
+        step("break", 10, RunState::RestLap),
+        step("review", 15, RunState::LAP),
+        step("long", 30, RunState::RestLoop),
+    ]);
+    let status = timer.status();
+    assert_eq!((status.label.as_str(), status.step, status.laps_per_loop), ("focus", Some(0), 2));
+    assert_eq!(timer.remaining(), minutes(50));
+
+    timer.start();
+    clock.advance(minutes(50));
+    timer.tick();
+    let status = timer.status();
+    assert_eq!((status.label.as_str(), status.run_state, status.position()), ("break", RunState::RestLap, (1, 1)));
+    assert_eq!(finished(&mut timer)[0].planned_sec, 50 * 60);
+    timer.skip();
+    timer.skip();
+    let status = timer.status();
+    assert_eq!((status.label.as_str(), status.planned, status.position()), ("long", minutes(30), (2, 1)));
+
+    // back to the top, one loop further on
+    timer.start();
+    timer.skip();
+    let status = timer.status();
+    assert_eq!((status.label.as_str(), status.position()), ("focus", (1, 2)));
+
+    // a stop starts the sequence over but keeps it
+    timer.skip();
+    timer.stop();
+    assert_eq!(timer.status().label, "focus");
+    assert_eq!(timer.sequence().len(), 4);
+    timer.set_sequence(Vec::new());
+    assert_eq!(timer.status().laps_per_loop, Config::default().laps_per_loop);
+    assert_eq!(timer.remaining(), minutes(25));
+}
+
+#[test]
+fn restoring_into_a_shorter_sequence_starts_it_over() {
+    let (clock, mut timer) = fake_timer();
+    timer.set_sequence(vec![
+        step("focus", 50, RunState::LAP),
+        step("break", 10, RunState::RestLap),
+        step("long", 30, RunState::RestLoop),
+    ]);
+    timer.start();
+    timer.skip();
+    timer.skip();
+    let checkpoint = timer.checkpoint().unwrap();
+    assert_eq!((checkpoint.step, checkpoint.run_state), (2, RunState::RestLoop));
+
+    let mut restored = Timer::with_clock(Config::default(), clock.clone());
+    restored.set_sequence(vec![step("focus", 50, RunState::LAP), step("break", 10, RunState::RestLap)]);
+    restored.restore(&checkpoint);
+    let status = restored.status();
+    assert_eq!((status.label.as_str(), status.run_state, status.step), ("focus", RunState::LAP, Some(0)));
+    assert_eq!(restored.remaining(), minutes(30));
+    restored.skip();
+    assert_eq!((restored.run_state(), restored.remaining()), (RunState::RestLap, minutes(10)));
+}
+
+#[test]
+fn restoring_across_a_sequence_added_or_removed() {
+    let (clock, mut timer) = fake_timer();
+    timer.start();
+    timer.skip();
+    let plain = timer.checkpoint().unwrap();
+    assert_eq!(plain.run_state, RunState::RestLap);
+
+    let mut restored = Timer::with_clock(Config::default(), clock.clone());
+    restored.set_sequence(vec![step("focus", 50, RunState::LAP), step("break", 10, RunState::RestLap)]);
+    restored.restore(&plain);
+    let status = restored.status();
+    assert_eq!((status.label.as_str(), status.run_state, status.step), ("focus", RunState::LAP, Some(0)));
+
+    restored.start();
+    restored.skip();
+    let stepped = restored.checkpoint().unwrap();
+    assert_eq!((stepped.step, stepped.run_state), (1, RunState::RestLap));
+    let mut restored = Timer::with_clock(Config::default(), clock.clone());
+    restored.restore(&stepped);
+    let status = restored.status();
+    assert_eq!((status.label.as_str(), status.run_state, status.step), (RunState::RestLap.label(), RunState::RestLap, None));
+    restored.skip();
+    assert_eq!((restored.run_state(), restored.remaining()), (RunState::LAP, minutes(25)));
+}
@@ -0,0 +1,90 @@
+use std::{sync::{Arc, mpsc}, time::Duration};
+use pomodoro_rs::{
+    clock::FakeClock,
+    timer::{Config, Event, RunState, Timer},
+    worker::{Command, Worker},
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Next event that isn't a history record.
+fn next(events: &mpsc::Receiver<Event>) -> Result<Event, mpsc::RecvTimeoutError> {
+    loop {
+        match events.recv_timeout(TIMEOUT)? {
+            Event::Finished(_) => continue,
+            event => return Ok(event),
+        }
+    }
+}
+
+fn fake_worker() -> (Arc<FakeClock>, Worker, mpsc::Receiver<Event>) {
+    let clock = Arc::new(FakeClock::new());
+    let worker = Worker::spawn(Timer::with_clock(Config::default(), clock.clone()), || {});
+    let events = worker.subscribe();
+    (clock, worker, events)
+}
+
+#[test]
+fn commands_drive_the_timer() {
+    let (_clock, worker, events) = fake_worker();
+    worker.send(Command::Start);
+    assert_eq!(next(&events), Ok(Event::Started(RunState::LAP)));
+    assert!(worker.status().running);
+
+    worker.send(Command::Skip);
+    assert_eq!(next(&events), Ok(Event::Skipped(RunState::LAP)));
+    assert_eq!(
+        next(&events),
+        Ok(Event::PhaseEnded { ended: RunState::LAP, next: RunState::RestLap }),
+    );
+    let status = worker.status();
+    assert_eq!(status.run_state, RunState::RestLap);
+    assert!(status.pause);
+
+    worker.send(Command::Resume);
+    assert_eq!(next(&events), Ok(Event::Started(RunState::RestLap)));
+    worker.send(Command::Stop);
+    assert_eq!(next(&events), Ok(Event::Stopped));
+    assert!(!worker.status().running);
+}
+
+#[test]
+fn start_does_not_resume() {
+    let (_clock, worker, events) = fake_worker();
+    worker.send(Command::Resume);
+    worker.send(Command::Start);
+    assert_eq!(next(&events), Ok(Event::Started(RunState::LAP)));
+    worker.send(Command::Pause);
+    assert_eq!(next(&events), Ok(Event::Paused(RunState::LAP)));
+    worker.send(Command::Start);
+    worker.send(Command::Stop);
+    assert_eq!(next(&events), Ok(Event::Stopped));
+}
+
+#[test]
+fn drop_leaves_the_session_and_shuts_down() {
+    let (_clock, worker, events) = fake_worker();
+    let handle = worker.handle();
+    worker.send(Command::Start);
+    assert_eq!(next(&events), Ok(Event::Started(RunState::LAP)));
+    drop(worker);
+    // nothing is logged, the session is still there to be checkpointed
+    assert_eq!(events.recv_timeout(TIMEOUT), Err(mpsc::RecvTimeoutError::Disconnected));
+    assert!(handle.checkpoint().is_some());
+}
+
+#[test]
+fn events_come_with_the_status_they_left() {
+    let (_clock, worker, _events) = fake_worker();
+    let events = worker.handle().subscribe_with_status();
+    worker.send(Command::Start);
+    worker.send(Command::Skip);
+    worker.send(Command::Skip);
+    let statuses: Vec<_> = events
+        .iter()
+        .filter(|(event, _)| matches!(event, Event::PhaseEnded { .. }))
+        .take(2)
+        .map(|(_, status)| (status.run_state, status.planned))
+        .collect();
+    assert_eq!(statuses, [(RunState::RestLap, Duration::from_secs(5 * 60)), (RunState::LAP, Duration::from_secs(25 * 60))]);
+}